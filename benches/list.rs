@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use kdb::{KBox, List};
+
+fn build_lists(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build list");
+    for len in [1_000usize, 100_000] {
+        let data: Vec<i64> = (0..len as i64).collect();
+
+        group.bench_with_input(BenchmarkId::new("extend", len), &data, |b, data| {
+            b.iter(|| {
+                let mut list = KBox::<List<i64>>::new_list();
+                list.extend(data.iter().copied());
+                black_box(list)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("with_capacity + extend", len), &data, |b, data| {
+            b.iter(|| {
+                let mut list = KBox::<List<i64>>::with_capacity(data.len());
+                list.extend(data.iter().copied());
+                black_box(list)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("extend_from_slice", len), &data, |b, data| {
+            b.iter(|| {
+                let mut list = KBox::<List<i64>>::new_list();
+                list.extend_from_slice(data);
+                black_box(list)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("from vec", len), &data, |b, data| {
+            b.iter(|| black_box(KBox::<List<i64>>::from(data.clone())))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, build_lists);
+criterion_main!(benches);
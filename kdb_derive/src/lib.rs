@@ -0,0 +1,338 @@
+//! Derive macros for the `IntoKdb` and `FromKdb` traits in the `kdb` crate.
+//!
+//! Don't use this crate directly, enable the `derive` feature of `kdb` instead.
+//!
+//! * Structs with named fields are converted to dictionaries keyed by symbols with the names of the fields.
+//! * Tuple structs with a single field are converted to the same K object as that field.
+//! * Other tuple structs are converted to mixed lists.
+//! * Enum variants without fields are converted to symbols with the name of the variant.
+//! * Enum variants with fields are converted to dictionaries with a `tag` key containing the name of the variant as
+//!   a symbol. Named fields are stored under their own names, unnamed fields under a `value` key (as a mixed list if
+//!   there is more than one).
+//!
+//! The following attributes are supported:
+//! * `#[kdb(rename = "name")]` on a field or variant changes the key or symbol it is stored as.
+//! * `#[kdb(tag = "name")]` on an enum changes the name of the key used to store the variant name.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DataEnum, DeriveInput, Error, Fields, Generics, Ident, LitStr,
+};
+
+/// Derives `IntoKdb` for a struct or enum.
+///
+/// # Example
+/// ```ignore
+/// use kdb::{IntoKdb, symbol, Symbol};
+///
+/// #[derive(IntoKdb)]
+/// struct Subscription {
+///     table: Symbol,
+///     #[kdb(rename = "syms")]
+///     symbols: Vec<Symbol>,
+/// }
+///
+/// // Creates the dictionary `table`syms!(`trade;`a`b)
+/// let k = Subscription { table: symbol("trade"), symbols: vec![symbol("a"), symbol("b")] }.into_kdb();
+/// ```
+#[proc_macro_derive(IntoKdb, attributes(kdb))]
+pub fn derive_into_kdb(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_kdb(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Derives `FromKdb` for a struct or enum.
+///
+/// Conversions of structs will fail with `ConversionError::MissingKey` if a key is missing from the dictionary,
+/// unless the field is an `Option`, and `ConversionError::InvalidValue` naming the key if the value has the wrong type.
+///
+/// # Example
+/// ```ignore
+/// use kdb::{FromKdb, Symbol};
+///
+/// #[derive(FromKdb)]
+/// enum Side {
+///     Buy,
+///     Sell,
+/// }
+///
+/// let side = Side::from_kdb(&k)?;
+/// ```
+#[proc_macro_derive(FromKdb, attributes(kdb))]
+pub fn derive_from_kdb(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_kdb(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Attributes that can be applied with `#[kdb(...)]`.
+#[derive(Default)]
+struct KdbAttrs {
+    rename: Option<String>,
+    tag: Option<String>,
+}
+
+impl KdbAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = KdbAttrs::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("kdb")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    result.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if meta.path.is_ident("tag") {
+                    result.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported kdb attribute, expected `rename` or `tag`"))
+                }
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// Adds a bound of `bound` to every type parameter.
+fn add_bounds(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+    generics
+}
+
+/// The name of each field and the key it is stored under.
+fn named_fields(fields: &Fields) -> Result<Vec<(Ident, String)>, Error> {
+    fields
+        .iter()
+        .map(|f| {
+            let ident = f.ident.clone().unwrap();
+            let key = KdbAttrs::parse(&f.attrs)?.rename.unwrap_or_else(|| ident.to_string());
+            Ok((ident, key))
+        })
+        .collect()
+}
+
+fn variant_name(variant: &syn::Variant) -> Result<String, Error> {
+    Ok(KdbAttrs::parse(&variant.attrs)?
+        .rename
+        .unwrap_or_else(|| variant.ident.to_string()))
+}
+
+fn tag_key(input: &DeriveInput) -> Result<String, Error> {
+    Ok(KdbAttrs::parse(&input.attrs)?.tag.unwrap_or_else(|| "tag".to_owned()))
+}
+
+fn unit_struct_error(input: &DeriveInput) -> Error {
+    Error::new_spanned(&input.ident, "kdb conversions can't be derived for unit structs")
+}
+
+fn into_kdb(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::kdb::IntoKdb));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(_) => {
+                let inserts = named_fields(&data.fields)?.into_iter().map(|(ident, key)| {
+                    quote! {
+                        dict.insert(::kdb::__private::symbol(#key), ::kdb::IntoKdb::into_kdb(self.#ident));
+                    }
+                });
+                quote! {
+                    let mut dict = ::kdb::__private::new_dict();
+                    #(#inserts)*
+                    dict.into()
+                }
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {
+                ::kdb::IntoKdb::into_kdb(self.0)
+            },
+            Fields::Unnamed(fields) => {
+                let items = (0..fields.unnamed.len()).map(syn::Index::from);
+                quote! {
+                    ::kdb::__private::new_list(vec![#(::kdb::IntoKdb::into_kdb(self.#items)),*])
+                }
+            }
+            Fields::Unit => return Err(unit_struct_error(input)),
+        },
+        Data::Enum(data) => enum_into_kdb(input, data)?,
+        Data::Union(_) => return Err(Error::new_spanned(input, "kdb conversions can't be derived for unions")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::kdb::IntoKdb for #name #ty_generics #where_clause {
+            fn into_kdb(self) -> ::kdb::KBox<::kdb::Any> {
+                #body
+            }
+        }
+    })
+}
+
+fn enum_into_kdb(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let tag = tag_key(input)?;
+    let arms = data
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = &variant.ident;
+            let variant_name = variant_name(variant)?;
+            let tagged_dict = quote! {
+                let mut dict = ::kdb::__private::new_dict();
+                dict.insert(::kdb::__private::symbol(#tag), ::kdb::__private::symbol(#variant_name));
+            };
+            Ok(match &variant.fields {
+                Fields::Unit => quote! {
+                    #name::#ident => ::kdb::IntoKdb::into_kdb(::kdb::__private::symbol(#variant_name)),
+                },
+                Fields::Named(_) => {
+                    let fields = named_fields(&variant.fields)?;
+                    let idents: Vec<_> = fields.iter().map(|(ident, _)| ident).collect();
+                    let inserts = fields.iter().map(|(ident, key)| {
+                        quote! {
+                            dict.insert(::kdb::__private::symbol(#key), ::kdb::IntoKdb::into_kdb(#ident));
+                        }
+                    });
+                    quote! {
+                        #name::#ident { #(#idents),* } => {
+                            #tagged_dict
+                            #(#inserts)*
+                            dict.into()
+                        }
+                    }
+                }
+                Fields::Unnamed(fields) => {
+                    let idents: Vec<_> = (0..fields.unnamed.len()).map(|i| format_ident!("f{}", i)).collect();
+                    let value = if idents.len() == 1 {
+                        quote!(::kdb::IntoKdb::into_kdb(f0))
+                    } else {
+                        quote!(::kdb::__private::new_list(vec![#(::kdb::IntoKdb::into_kdb(#idents)),*]))
+                    };
+                    quote! {
+                        #name::#ident(#(#idents),*) => {
+                            #tagged_dict
+                            dict.insert(::kdb::__private::symbol("value"), #value);
+                            dict.into()
+                        }
+                    }
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+fn from_kdb(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::kdb::FromKdb));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(_) => {
+                let fields = named_fields(&data.fields)?.into_iter().map(|(ident, key)| {
+                    quote! {
+                        #ident: ::kdb::__private::get_field(dict, #key)?,
+                    }
+                });
+                quote! {
+                    let dict = ::kdb::__private::as_dict(any)?;
+                    Ok(#name { #(#fields)* })
+                }
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {
+                Ok(#name(::kdb::FromKdb::from_kdb(any)?))
+            },
+            Fields::Unnamed(fields) => {
+                let len = fields.unnamed.len();
+                let items = (0..len).map(|i| quote!(::kdb::__private::get_item(list, #i)?));
+                quote! {
+                    let list = ::kdb::__private::as_list(any, #len)?;
+                    Ok(#name(#(#items),*))
+                }
+            }
+            Fields::Unit => return Err(unit_struct_error(input)),
+        },
+        Data::Enum(data) => enum_from_kdb(input, data)?,
+        Data::Union(_) => return Err(Error::new_spanned(input, "kdb conversions can't be derived for unions")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::kdb::FromKdb for #name #ty_generics #where_clause {
+            fn from_kdb(any: &::kdb::Any) -> ::std::result::Result<Self, ::kdb::ConversionError> {
+                #body
+            }
+        }
+    })
+}
+
+fn enum_from_kdb(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let tag = tag_key(input)?;
+    let mut unit_arms = Vec::new();
+    let mut tagged_arms = Vec::new();
+
+    for variant in &data.variants {
+        let ident = &variant.ident;
+        let variant_name = variant_name(variant)?;
+        match &variant.fields {
+            Fields::Unit => {
+                let arm = quote! {
+                    #variant_name => Ok(#name::#ident),
+                };
+                unit_arms.push(arm.clone());
+                tagged_arms.push(arm);
+            }
+            Fields::Named(_) => {
+                let fields = named_fields(&variant.fields)?.into_iter().map(|(ident, key)| {
+                    quote! {
+                        #ident: ::kdb::__private::get_field(dict, #key)?,
+                    }
+                });
+                tagged_arms.push(quote! {
+                    #variant_name => Ok(#name::#ident { #(#fields)* }),
+                });
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                tagged_arms.push(quote! {
+                    #variant_name => Ok(#name::#ident(::kdb::__private::get_field(dict, "value")?)),
+                });
+            }
+            Fields::Unnamed(fields) => {
+                let len = fields.unnamed.len();
+                let items = (0..len).map(|i| quote!(::kdb::__private::get_item(list, #i)?));
+                tagged_arms.push(quote! {
+                    #variant_name => ::kdb::__private::with_list_field(dict, "value", #len, |list| {
+                        Ok(#name::#ident(#(#items),*))
+                    }),
+                });
+            }
+        }
+    }
+
+    Ok(quote! {
+        if let Some(variant) = ::kdb::__private::as_symbol(any) {
+            return match variant.as_str() {
+                #(#unit_arms)*
+                _ => Err(::kdb::ConversionError::UnknownVariant(variant)),
+            };
+        }
+        let dict = ::kdb::__private::as_dict(any)?;
+        let variant = ::kdb::__private::get_tag(dict, #tag)?;
+        match variant.as_str() {
+            #(#tagged_arms)*
+            _ => Err(::kdb::ConversionError::UnknownVariant(variant)),
+        }
+    })
+}
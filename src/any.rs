@@ -1,24 +1,85 @@
 use crate::atom::Atom;
+use crate::k_type::*;
 use crate::kapi;
 use crate::kbox::KBox;
 use crate::type_traits::*;
 use crate::{error::ConversionError, Dictionary};
 use crate::{k::K, Table};
-use crate::{k_type::KTypeCode, k_type::MIXED_LIST};
 use crate::{list::List, KError};
 use std::fmt;
-use std::mem;
+use std::{mem, slice};
 
 /// Any represents any KDB value regardless of type.
 /// Unlike atoms or lists you can't do anything with it, except for convert it into an atom or a list.
 /// It is ABI compatible with a K object, so it can be safely used as a parameter or return type for a function.
 /// See the chapter on embedded functions for more information.
 #[repr(transparent)]
-#[derive(PartialEq)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct Any {
     k: K,
 }
 
+impl Any {
+    /// The KDB type code of the underlying K object.
+    #[inline]
+    pub(crate) fn k_type(&self) -> KTypeCode {
+        self.k.t
+    }
+
+    /// The number of items if the object is a simple or mixed list.
+    #[inline]
+    pub(crate) fn list_len(&self) -> Option<usize> {
+        if self.k.t == MIXED_LIST || self.k.t.is_simple_list() {
+            Some(unsafe { self.k.union.list.n as usize })
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the value is the generic null `(::)`. Unary primitives such as `-:` share its type code,
+    /// and are told apart by the primitive's index, which is zero for `(::)`.
+    pub(crate) fn is_generic_null(&self) -> bool {
+        self.k.t == GENERIC_NULL && unsafe { self.k.union.g } == 0
+    }
+
+    /// Returns true if the value is the generic null `(::)`, or an atom holding the null value for its type.
+    /// Boolean and byte atoms have no null value.
+    pub fn is_null(&self) -> bool {
+        let t = self.k.t;
+        if t == GENERIC_NULL {
+            return self.is_generic_null();
+        }
+        if i32::from(t) >= 0 || t == ERROR {
+            return false;
+        }
+        unsafe {
+            let u = &self.k.union;
+            match t {
+                SHORT_ATOM => u.h == i16::MIN,
+                INT_ATOM | MONTH_ATOM | DATE_ATOM | MINUTE_ATOM | SECOND_ATOM | TIME_ATOM => u.i == i32::MIN,
+                LONG_ATOM | TIMESTAMP_ATOM | TIMESPAN_ATOM => u.j == i64::MIN,
+                REAL_ATOM => u.e.is_nan(),
+                FLOAT_ATOM | DATE_TIME_ATOM => u.f.is_nan(),
+                CHAR_ATOM => u.c == b' ' as i8,
+                SYMBOL_ATOM => *u.s == 0,
+                GUID_ATOM => slice::from_raw_parts(&u.list.g0 as *const u8, 16)
+                    .iter()
+                    .all(|b| *b == 0),
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Creates the generic null `(::)`.
+pub(crate) fn generic_null() -> KBox<Any> {
+    unsafe {
+        let k = kapi::ka(GENERIC_NULL.into());
+        (*k).union.g = 0;
+        KBox::from_raw(k)
+    }
+}
+
 impl fmt::Debug for Any {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Any(Type={})", self.k.t)
@@ -105,25 +166,25 @@ impl KObject for Any {
 
 impl<T: KListable> AsRef<Any> for KBox<List<T>> {
     fn as_ref(&self) -> &Any {
-        unsafe { &*(self as *const _ as *const _) }
+        (**self).as_ref()
     }
 }
 
 impl<T: KValue> AsRef<Any> for KBox<Atom<T>> {
     fn as_ref(&self) -> &Any {
-        unsafe { &*(self as *const _ as *const _) }
+        (**self).as_ref()
     }
 }
 
 impl AsRef<Any> for KBox<Dictionary> {
     fn as_ref(&self) -> &Any {
-        unsafe { &*(self as *const _ as *const _) }
+        (**self).as_ref()
     }
 }
 
 impl AsRef<Any> for KBox<Table> {
     fn as_ref(&self) -> &Any {
-        unsafe { &*(self as *const _ as *const _) }
+        (**self).as_ref()
     }
 }
 
@@ -147,15 +208,9 @@ impl<T: KObject + KTyped> KdbCast<T> for KBox<Any> {
     type Output = KBox<T>;
     fn try_cast(self) -> Result<Self::Output, ConversionError> {
         unsafe {
-            if (*self.k_ptr()).t != T::K_TYPE {
-                Err(ConversionError::InvalidKCast {
-                    from: (*self.k_ptr()).t,
-                    to: T::K_TYPE,
-                })
-            } else {
-                #[allow(clippy::clippy::transmute_ptr_to_ptr)]
-                Ok(mem::transmute(self))
-            }
+            T::check_type(&*self.k_ptr())?;
+            #[allow(clippy::transmute_ptr_to_ptr)]
+            Ok(mem::transmute::<Self, Self::Output>(self))
         }
     }
 }
@@ -164,15 +219,9 @@ impl<'a, T: 'a + KObject + KTyped> KdbCast<T> for &'a KBox<Any> {
     type Output = &'a KBox<T>;
     fn try_cast(self) -> Result<Self::Output, ConversionError> {
         unsafe {
-            if (*self.k_ptr()).t != T::K_TYPE {
-                Err(ConversionError::InvalidKCast {
-                    from: (*self.k_ptr()).t,
-                    to: T::K_TYPE,
-                })
-            } else {
-                #[allow(clippy::clippy::transmute_ptr_to_ptr)]
-                Ok(mem::transmute(self))
-            }
+            T::check_type(&*self.k_ptr())?;
+            #[allow(clippy::transmute_ptr_to_ptr)]
+            Ok(mem::transmute::<Self, Self::Output>(self))
         }
     }
 }
@@ -181,15 +230,9 @@ impl<'a, T: 'a + KObject + KTyped> KdbCast<T> for &'a Any {
     type Output = &'a T;
     fn try_cast(self) -> Result<Self::Output, ConversionError> {
         unsafe {
-            if (*self.k_ptr()).t != T::K_TYPE {
-                Err(ConversionError::InvalidKCast {
-                    from: (*self.k_ptr()).t,
-                    to: T::K_TYPE,
-                })
-            } else {
-                #[allow(clippy::clippy::transmute_ptr_to_ptr)]
-                Ok(mem::transmute(self))
-            }
+            T::check_type(&*self.k_ptr())?;
+            #[allow(clippy::transmute_ptr_to_ptr)]
+            Ok(mem::transmute::<Self, Self::Output>(self))
         }
     }
 }
@@ -0,0 +1,252 @@
+//! Matching on the type of an `Any`.
+use crate::any::Any;
+use crate::atom::Atom;
+use crate::date_time_types::*;
+use crate::dictionary::Dictionary;
+use crate::k_error::KError;
+use crate::k_type::*;
+use crate::kbox::KBox;
+use crate::list::List;
+use crate::symbol::Symbol;
+use crate::table::Table;
+use std::mem;
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+/// The kind of K object a type code represents, for the types that aren't covered by a variant of their own.
+enum Kind {
+    Enum,
+    EnumList,
+    Function,
+    Unknown,
+}
+
+fn other_kind(t: KTypeCode) -> Kind {
+    match i32::from(t) {
+        -76..=-20 => Kind::Enum,
+        20..=76 => Kind::EnumList,
+        100..=112 => Kind::Function,
+        _ => Kind::Unknown,
+    }
+}
+
+macro_rules! any_view {
+    ($($(#[$attr:meta])* $variant:ident($t:ty) = $code:ident),* $(,)?) => {
+        /// A borrowed view of an `Any`, with one variant per KDB type. Created with `Any::view`.
+        ///
+        /// The GUID variants only exist with the `uuid` feature, so the enum is marked non-exhaustive: turning the
+        /// feature on elsewhere in the dependency graph must not break a `match` that doesn't use it. Without the
+        /// feature, GUIDs are viewed as `Unknown`.
+        ///
+        /// # Example
+        /// ```
+        /// use kdb::{parse_literal, AnyRef};
+        ///
+        /// let a = parse_literal("1 2 3").unwrap();
+        /// match a.view() {
+        ///     AnyRef::LongList(l) => assert_eq!(l.as_slice(), &[1, 2, 3]),
+        ///     _ => panic!("expected a long list"),
+        /// }
+        /// ```
+        #[non_exhaustive]
+        pub enum AnyRef<'a> {
+            $(
+                $(#[$attr])*
+                #[allow(missing_docs)]
+                $variant(&'a $t),
+            )*
+            /// The generic null `(::)`.
+            Null(&'a Any),
+            /// An enumerated atom, such as `` `sym$`a ``.
+            Enum(&'a Any),
+            /// An enumerated list.
+            EnumList(&'a Any),
+            /// A lambda, primitive, projection, composition, iterator or foreign function.
+            Function(&'a Any),
+            /// An object with a type code that isn't supported, holding the type code.
+            Unknown(i8),
+        }
+
+        /// An owned version of `AnyRef`, created by converting a `KBox<Any>`. Like `AnyRef`, it is non-exhaustive
+        /// because the GUID variants depend on the `uuid` feature.
+        ///
+        /// # Example
+        /// ```
+        /// use kdb::{parse_literal, AnyBox};
+        ///
+        /// match AnyBox::from(parse_literal("`a`b!1 2").unwrap()) {
+        ///     AnyBox::Dict(d) => assert_eq!(d.len(), 2),
+        ///     _ => panic!("expected a dictionary"),
+        /// }
+        /// ```
+        #[non_exhaustive]
+        pub enum AnyBox {
+            $(
+                $(#[$attr])*
+                #[allow(missing_docs)]
+                $variant(KBox<$t>),
+            )*
+            /// The generic null `(::)`.
+            Null(KBox<Any>),
+            /// An enumerated atom, such as `` `sym$`a ``.
+            Enum(KBox<Any>),
+            /// An enumerated list.
+            EnumList(KBox<Any>),
+            /// A lambda, primitive, projection, composition, iterator or foreign function.
+            Function(KBox<Any>),
+            /// An object with a type code that isn't supported.
+            Unknown(KBox<Any>),
+        }
+
+        impl Any {
+            /// Returns a view of the value that can be matched on to find its type, rather than trying to cast it
+            /// to each type in turn.
+            pub fn view(&self) -> AnyRef<'_> {
+                unsafe {
+                    match self.k_type() {
+                        $(
+                            $(#[$attr])*
+                            $code => AnyRef::$variant(&*(self as *const Any as *const $t)),
+                        )*
+                        GENERIC_NULL if self.is_generic_null() => AnyRef::Null(self),
+                        t => match other_kind(t) {
+                            Kind::Enum => AnyRef::Enum(self),
+                            Kind::EnumList => AnyRef::EnumList(self),
+                            Kind::Function => AnyRef::Function(self),
+                            Kind::Unknown => AnyRef::Unknown(i32::from(t) as i8),
+                        },
+                    }
+                }
+            }
+        }
+
+        impl From<KBox<Any>> for AnyBox {
+            fn from(any: KBox<Any>) -> Self {
+                unsafe {
+                    match any.k_type() {
+                        $(
+                            $(#[$attr])*
+                            $code => AnyBox::$variant(mem::transmute::<KBox<Any>, KBox<$t>>(any)),
+                        )*
+                        GENERIC_NULL if any.is_generic_null() => AnyBox::Null(any),
+                        t => match other_kind(t) {
+                            Kind::Enum => AnyBox::Enum(any),
+                            Kind::EnumList => AnyBox::EnumList(any),
+                            Kind::Function => AnyBox::Function(any),
+                            Kind::Unknown => AnyBox::Unknown(any),
+                        },
+                    }
+                }
+            }
+        }
+
+        impl From<AnyBox> for KBox<Any> {
+            fn from(any: AnyBox) -> Self {
+                match any {
+                    $(
+                        $(#[$attr])*
+                        AnyBox::$variant(v) => v.into(),
+                    )*
+                    AnyBox::Null(v) | AnyBox::Enum(v) | AnyBox::EnumList(v) | AnyBox::Function(v) | AnyBox::Unknown(v) => v,
+                }
+            }
+        }
+    };
+}
+
+any_view! {
+    BoolAtom(Atom<bool>) = BOOLEAN_ATOM,
+    #[cfg(feature = "uuid")]
+    GuidAtom(Atom<Uuid>) = GUID_ATOM,
+    ByteAtom(Atom<u8>) = BYTE_ATOM,
+    ShortAtom(Atom<i16>) = SHORT_ATOM,
+    IntAtom(Atom<i32>) = INT_ATOM,
+    LongAtom(Atom<i64>) = LONG_ATOM,
+    RealAtom(Atom<f32>) = REAL_ATOM,
+    FloatAtom(Atom<f64>) = FLOAT_ATOM,
+    CharAtom(Atom<i8>) = CHAR_ATOM,
+    SymbolAtom(Atom<Symbol>) = SYMBOL_ATOM,
+    TimestampAtom(Atom<Timestamp>) = TIMESTAMP_ATOM,
+    MonthAtom(Atom<Month>) = MONTH_ATOM,
+    DateAtom(Atom<Date>) = DATE_ATOM,
+    DateTimeAtom(Atom<DateTime>) = DATE_TIME_ATOM,
+    TimespanAtom(Atom<Timespan>) = TIMESPAN_ATOM,
+    MinuteAtom(Atom<Minute>) = MINUTE_ATOM,
+    SecondAtom(Atom<Second>) = SECOND_ATOM,
+    TimeAtom(Atom<Time>) = TIME_ATOM,
+    MixedList(List<Any>) = MIXED_LIST,
+    BoolList(List<bool>) = BOOLEAN_LIST,
+    #[cfg(feature = "uuid")]
+    GuidList(List<Uuid>) = GUID_LIST,
+    ByteList(List<u8>) = BYTE_LIST,
+    ShortList(List<i16>) = SHORT_LIST,
+    IntList(List<i32>) = INT_LIST,
+    LongList(List<i64>) = LONG_LIST,
+    RealList(List<f32>) = REAL_LIST,
+    FloatList(List<f64>) = FLOAT_LIST,
+    CharList(List<i8>) = CHAR_LIST,
+    SymbolList(List<Symbol>) = SYMBOL_LIST,
+    TimestampList(List<Timestamp>) = TIMESTAMP_LIST,
+    MonthList(List<Month>) = MONTH_LIST,
+    DateList(List<Date>) = DATE_LIST,
+    DateTimeList(List<DateTime>) = DATE_TIME_LIST,
+    TimespanList(List<Timespan>) = TIMESPAN_LIST,
+    MinuteList(List<Minute>) = MINUTE_LIST,
+    SecondList(List<Second>) = SECOND_LIST,
+    TimeList(List<Time>) = TIME_LIST,
+    Dict(Dictionary) = DICT,
+    Table(Table) = TABLE,
+    Error(KError) = ERROR,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_traits::KObject;
+    use crate::{parse_literal, symbol};
+
+    #[test]
+    fn view_matches_the_type() {
+        let a = parse_literal("`a`b").unwrap();
+        match a.view() {
+            AnyRef::SymbolList(l) => assert_eq!(l.as_slice(), &[symbol("a"), symbol("b")]),
+            _ => panic!("expected a symbol list"),
+        }
+        assert!(matches!(parse_literal("1i").unwrap().view(), AnyRef::IntAtom(a) if a.value() == 1));
+        assert!(matches!(parse_literal("([] a:1 2)").unwrap().view(), AnyRef::Table(_)));
+        assert!(matches!(parse_literal("::").unwrap().view(), AnyRef::Null(_)));
+    }
+
+    #[test]
+    fn any_box_round_trips() {
+        let a = AnyBox::from(parse_literal("(1;`a)").unwrap());
+        let list = match a {
+            AnyBox::MixedList(l) => l,
+            _ => panic!("expected a mixed list"),
+        };
+        assert_eq!(list.len(), 2);
+        let any = KBox::<Any>::from(AnyBox::MixedList(list));
+        assert_eq!(any, parse_literal("(1;`a)").unwrap());
+    }
+
+    fn set_type(a: &mut Any, t: i8) {
+        unsafe { *(&mut (*a.k_ptr_mut()).t as *mut KTypeCode as *mut i8) = t };
+    }
+
+    #[test]
+    fn enums_and_functions_have_their_own_variants() {
+        let mut a = parse_literal("1").unwrap();
+        set_type(&mut a, -20);
+        assert!(matches!(a.view(), AnyRef::Enum(_)));
+        set_type(&mut a, 100);
+        assert!(matches!(a.view(), AnyRef::Function(_)));
+        // a unary primitive, such as -:, has the generic null's type but a nonzero index.
+        let mut null = parse_literal("::").unwrap();
+        unsafe { (*null.k_ptr_mut()).union.g = 2 };
+        assert!(matches!(null.view(), AnyRef::Function(_)));
+        assert!(matches!(AnyBox::from(null), AnyBox::Function(_)));
+        set_type(&mut a, 90);
+        assert!(matches!(a.view(), AnyRef::Unknown(90)));
+        set_type(&mut a, -7);
+    }
+}
@@ -0,0 +1,578 @@
+//! Conversions between K lists and tables and Apache Arrow arrays and record batches.
+//!
+//! | q type    | Arrow type                                      |
+//! |-----------|-------------------------------------------------|
+//! | boolean   | `Boolean`                                       |
+//! | guid      | `FixedSizeBinary(16)`                           |
+//! | byte      | `UInt8`                                         |
+//! | short     | `Int16`                                         |
+//! | int       | `Int32`                                         |
+//! | long      | `Int64`                                         |
+//! | real      | `Float32`                                       |
+//! | float     | `Float64`                                       |
+//! | char      | `Utf8` (one character per item)                 |
+//! | symbol    | `Dictionary(Int32, Utf8)`                       |
+//! | timestamp | `Timestamp(Nanosecond)`                         |
+//! | month     | `Date32` (the first day of the month)           |
+//! | date      | `Date32`                                        |
+//! | datetime  | `Timestamp(Millisecond)`                        |
+//! | timespan  | `Duration(Nanosecond)`                          |
+//! | minute    | `Time32(Second)`                                |
+//! | second    | `Time32(Second)`                                |
+//! | time      | `Time32(Millisecond)`                           |
+//! | strings   | `Utf8` (a mixed list of char lists)             |
+//! | bytes     | `Binary` (a mixed list of byte lists)           |
+//!
+//! Temporal values are shifted between the KDB epoch (2000.01.01) and the unix epoch. The null value for each
+//! type becomes an Arrow null, and Arrow nulls become the null value for the q type (or `0b`/`0x00` for booleans
+//! and bytes, which have no null).
+use crate::any::Any;
+use crate::date_time_types::{civil_from_days, days_from_civil, K_DAY_OFFSET, K_NANO_OFFSET};
+use crate::error::ConversionError;
+use crate::k_type::*;
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::list::List;
+use crate::symbol::{symbol, Symbol};
+use crate::table::Table;
+use crate::try_cast;
+use crate::type_traits::KObject;
+use arrow_array::builder::StringDictionaryBuilder;
+use arrow_array::cast::AsArray;
+use arrow_array::types::*;
+use arrow_array::{
+    Array, ArrayRef, ArrowPrimitiveType, BinaryArray, BooleanArray, FixedSizeBinaryArray, PrimitiveArray, RecordBatch,
+    StringArray,
+};
+use arrow_buffer::{Buffer, NullBuffer, ScalarBuffer};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::{ptr, slice};
+
+/// The field metadata key used to record the q type character of a column in a record batch.
+/// This lets types that share an Arrow type (such as months and dates) convert back to the original q type.
+pub const KDB_TYPE_KEY: &str = "kdb_type";
+
+const K_MILLI_OFFSET: i64 = K_NANO_OFFSET / 1_000_000;
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// The q type characters, indexed by list type code.
+const TYPE_CHARS: &[u8; 20] = b" bg xhijefcspmdznuvt";
+
+/// Converts a K list into an Arrow array.
+///
+/// Simple lists, and mixed lists where every item is a string (or every item is a byte list), are supported.
+///
+/// # Example
+/// ```
+/// use kdb::{list, to_arrow_array};
+///
+/// let array = to_arrow_array(list![i32; 1, 2, i32::MIN].as_ref()).unwrap();
+/// assert_eq!(array.len(), 3);
+/// assert_eq!(array.null_count(), 1);
+/// ```
+pub fn to_arrow_array(any: &Any) -> Result<ArrayRef, ConversionError> {
+    Ok(match any.k_type() {
+        BOOLEAN_LIST => Arc::new(BooleanArray::from(items::<bool>(any).to_vec())),
+        GUID_LIST => {
+            let guids = items::<[u8; 16]>(any);
+            let nulls = null_buffer(guids, |g| g == [0; 16]);
+            Arc::new(FixedSizeBinaryArray::new(
+                16,
+                Buffer::from_slice_ref(guids.as_flattened()),
+                nulls,
+            ))
+        }
+        BYTE_LIST => copy_array::<UInt8Type>(items(any), |_| false),
+        SHORT_LIST => copy_array::<Int16Type>(items(any), |v| v == i16::MIN),
+        INT_LIST => copy_array::<Int32Type>(items(any), |v| v == i32::MIN),
+        LONG_LIST => copy_array::<Int64Type>(items(any), |v| v == i64::MIN),
+        REAL_LIST => copy_array::<Float32Type>(items(any), f32::is_nan),
+        FLOAT_LIST => copy_array::<Float64Type>(items(any), f64::is_nan),
+        CHAR_LIST => {
+            let chars = items::<u8>(any)
+                .iter()
+                .map(|c| std::str::from_utf8(slice::from_ref(c)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Arc::new(StringArray::from(chars))
+        }
+        SYMBOL_LIST => {
+            let mut builder = StringDictionaryBuilder::<Int32Type>::new();
+            for s in items::<Symbol>(any) {
+                match s.try_as_str()? {
+                    "" => builder.append_null(),
+                    s => builder.append_value(s),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        TIMESTAMP_LIST => {
+            map_array::<TimestampNanosecondType, i64>(items(any), |v| v == i64::MIN, |v| v.checked_add(K_NANO_OFFSET))?
+        }
+        MONTH_LIST => map_array::<Date32Type, i32>(
+            items(any),
+            |v| v == i32::MIN,
+            |m| {
+                let days = days_from_civil(2000 + i64::from(m).div_euclid(12), m.rem_euclid(12) as u32 + 1, 1);
+                i32::try_from(days).ok()
+            },
+        )?,
+        DATE_LIST => map_array::<Date32Type, i32>(items(any), |v| v == i32::MIN, |d| d.checked_add(K_DAY_OFFSET))?,
+        DATE_TIME_LIST => map_array::<TimestampMillisecondType, f64>(items(any), f64::is_nan, |v| {
+            let millis = (v * MILLIS_PER_DAY).round();
+            if millis.abs() < i64::MAX as f64 {
+                (millis as i64).checked_add(K_MILLI_OFFSET)
+            } else {
+                None
+            }
+        })?,
+        TIMESPAN_LIST => copy_array::<DurationNanosecondType>(items(any), |v| v == i64::MIN),
+        MINUTE_LIST => map_array::<Time32SecondType, i32>(items(any), |v| v == i32::MIN, |m| m.checked_mul(60))?,
+        SECOND_LIST => copy_array::<Time32SecondType>(items(any), |v| v == i32::MIN),
+        TIME_LIST => copy_array::<Time32MillisecondType>(items(any), |v| v == i32::MIN),
+        MIXED_LIST => {
+            let list = try_cast!(any; List<Any>)?;
+            if list.iter().all(|i| i.k_type() == CHAR_LIST) {
+                let strings = list
+                    .iter()
+                    .map(|s| try_cast!(s; List<i8>)?.try_as_str())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Arc::new(StringArray::from(strings))
+            } else if list.iter().all(|i| i.k_type() == BYTE_LIST) {
+                let bytes = list.iter().map(|b| items::<u8>(b)).collect();
+                Arc::new(BinaryArray::from_vec(bytes))
+            } else {
+                return Err(ConversionError::UnsupportedType(
+                    "mixed list of items other than strings or bytes".to_owned(),
+                ));
+            }
+        }
+        t => return Err(ConversionError::UnsupportedType(t.to_string())),
+    })
+}
+
+/// Converts an Arrow array into a K list. This is the inverse of `to_arrow_array`.
+///
+/// Arrow types that have no exact q equivalent are widened where possible: `Int8` becomes a short list,
+/// `UInt16` an int list and `UInt32`/`UInt64` long lists. Timestamps and durations of any unit are
+/// converted to nanoseconds.
+pub fn from_arrow_array(array: &dyn Array) -> Result<KBox<Any>, ConversionError> {
+    from_arrow(array, None)
+}
+
+fn from_arrow(array: &dyn Array, kdb_type: Option<u8>) -> Result<KBox<Any>, ConversionError> {
+    let nulls = array.logical_nulls();
+    let nulls = nulls.as_ref();
+    Ok(match (array.data_type(), kdb_type) {
+        (DataType::Boolean, _) => {
+            let array = array.as_boolean();
+            let mut list = new_list(BOOLEAN_LIST, array.len());
+            for (i, item) in items_mut::<bool>(&mut list).iter_mut().enumerate() {
+                *item = array.is_valid(i) && array.value(i);
+            }
+            list
+        }
+        (DataType::FixedSizeBinary(16), _) => {
+            let array = array.as_fixed_size_binary();
+            let mut list = new_list(GUID_LIST, array.len());
+            for (i, item) in items_mut::<[u8; 16]>(&mut list).iter_mut().enumerate() {
+                if array.is_valid(i) {
+                    item.copy_from_slice(array.value(i));
+                }
+            }
+            list
+        }
+        (DataType::UInt8, _) => copy_values(values::<UInt8Type>(array), nulls, BYTE_LIST, 0),
+        (DataType::Int8, _) => map_values(values::<Int8Type>(array), nulls, SHORT_LIST, i16::MIN, |v| {
+            Some(v.into())
+        })?,
+        (DataType::Int16, _) => copy_values(values::<Int16Type>(array), nulls, SHORT_LIST, i16::MIN),
+        (DataType::UInt16, _) => map_values(values::<UInt16Type>(array), nulls, INT_LIST, i32::MIN, |v| {
+            Some(v.into())
+        })?,
+        (DataType::Int32, _) => copy_values(values::<Int32Type>(array), nulls, INT_LIST, i32::MIN),
+        (DataType::UInt32, _) => map_values(values::<UInt32Type>(array), nulls, LONG_LIST, i64::MIN, |v| {
+            Some(v.into())
+        })?,
+        (DataType::Int64, _) => copy_values(values::<Int64Type>(array), nulls, LONG_LIST, i64::MIN),
+        (DataType::UInt64, _) => map_values(values::<UInt64Type>(array), nulls, LONG_LIST, i64::MIN, |v| {
+            i64::try_from(v).ok()
+        })?,
+        (DataType::Float32, _) => copy_values(values::<Float32Type>(array), nulls, REAL_LIST, f32::NAN),
+        (DataType::Float64, _) => copy_values(values::<Float64Type>(array), nulls, FLOAT_LIST, f64::NAN),
+        (DataType::Utf8, t) => strings(array.as_string::<i32>().iter(), t)?,
+        (DataType::LargeUtf8, t) => strings(array.as_string::<i64>().iter(), t)?,
+        (DataType::Dictionary(_, values), _) if **values == DataType::Utf8 => {
+            let array = array.as_any_dictionary();
+            let values = array.values().as_string::<i32>();
+            let mut list = new_list(SYMBOL_LIST, array.len());
+            for ((i, item), key) in items_mut::<Symbol>(&mut list)
+                .iter_mut()
+                .enumerate()
+                .zip(array.normalized_keys())
+            {
+                *item = if nulls.map(|n| n.is_valid(i)).unwrap_or(true) {
+                    to_symbol(values.value(key))?
+                } else {
+                    symbol("")
+                };
+            }
+            list
+        }
+        (DataType::Binary, _) => {
+            let mut list = KBox::<List<Any>>::new_list();
+            for bytes in array.as_binary::<i32>().iter() {
+                let bytes = bytes.unwrap_or_default();
+                let mut item = new_list(BYTE_LIST, bytes.len());
+                items_mut::<u8>(&mut item).copy_from_slice(bytes);
+                list.push(item);
+            }
+            list.into()
+        }
+        (DataType::Timestamp(unit, _), Some(b'z')) => {
+            let scale = nanos_per(unit) as f64 / 1e6;
+            map_values(timestamp_values(array, unit), nulls, DATE_TIME_LIST, f64::NAN, |v| {
+                Some((v as f64 * scale - K_MILLI_OFFSET as f64) / MILLIS_PER_DAY)
+            })?
+        }
+        (DataType::Timestamp(unit, _), _) => {
+            let scale = nanos_per(unit);
+            map_values(timestamp_values(array, unit), nulls, TIMESTAMP_LIST, i64::MIN, |v| {
+                v.checked_mul(scale)?.checked_sub(K_NANO_OFFSET)
+            })?
+        }
+        (DataType::Date32, Some(b'm')) => map_values(values::<Date32Type>(array), nulls, MONTH_LIST, i32::MIN, |d| {
+            let (year, month, _) = civil_from_days(d.into());
+            i32::try_from((year - 2000) * 12 + i64::from(month) - 1).ok()
+        })?,
+        (DataType::Date32, _) => map_values(values::<Date32Type>(array), nulls, DATE_LIST, i32::MIN, |d| {
+            d.checked_sub(K_DAY_OFFSET)
+        })?,
+        (DataType::Date64, _) => map_values(values::<Date64Type>(array), nulls, DATE_LIST, i32::MIN, |d| {
+            i32::try_from(d.div_euclid(86_400_000)).ok()?.checked_sub(K_DAY_OFFSET)
+        })?,
+        (DataType::Duration(TimeUnit::Nanosecond), _) => {
+            copy_values(values::<DurationNanosecondType>(array), nulls, TIMESPAN_LIST, i64::MIN)
+        }
+        (DataType::Duration(unit), _) => {
+            let scale = nanos_per(unit);
+            map_values(duration_values(array, unit), nulls, TIMESPAN_LIST, i64::MIN, |v| {
+                v.checked_mul(scale)
+            })?
+        }
+        (DataType::Time32(TimeUnit::Second), Some(b'u')) => {
+            map_values(values::<Time32SecondType>(array), nulls, MINUTE_LIST, i32::MIN, |s| {
+                Some(s.div_euclid(60))
+            })?
+        }
+        (DataType::Time32(TimeUnit::Second), _) => {
+            copy_values(values::<Time32SecondType>(array), nulls, SECOND_LIST, i32::MIN)
+        }
+        (DataType::Time32(TimeUnit::Millisecond), _) => {
+            copy_values(values::<Time32MillisecondType>(array), nulls, TIME_LIST, i32::MIN)
+        }
+        (DataType::Time64(TimeUnit::Microsecond), _) => map_values(
+            values::<Time64MicrosecondType>(array),
+            nulls,
+            TIMESPAN_LIST,
+            i64::MIN,
+            |v| v.checked_mul(1_000),
+        )?,
+        (DataType::Time64(TimeUnit::Nanosecond), _) => {
+            copy_values(values::<Time64NanosecondType>(array), nulls, TIMESPAN_LIST, i64::MIN)
+        }
+        (t, _) => return Err(ConversionError::UnsupportedType(t.to_string())),
+    })
+}
+
+impl Table {
+    /// Converts the table into an Arrow record batch, converting each column with `to_arrow_array`.
+    /// The q type of each simple column is stored in the field metadata under `KDB_TYPE_KEY`.
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ConversionError> {
+        let mut fields = Vec::with_capacity(self.columns().len());
+        let mut arrays = Vec::with_capacity(self.columns().len());
+        for (name, column) in self.column_names().iter().zip(self.columns().iter()) {
+            let name = name.try_as_str()?;
+            let array = to_arrow_array(column).map_err(|e| ConversionError::InvalidValue {
+                key: name.to_owned(),
+                source: Box::new(e),
+            })?;
+            let mut field = Field::new(name, array.data_type().clone(), true);
+            let t = column.k_type();
+            if t.is_simple_list() {
+                let type_char = char::from(TYPE_CHARS[i32::from(t) as usize]).to_string();
+                field = field.with_metadata(HashMap::from([(KDB_TYPE_KEY.to_owned(), type_char)]));
+            }
+            fields.push(field);
+            arrays.push(array);
+        }
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(|e| ConversionError::Custom(e.to_string()))
+    }
+}
+
+impl KBox<Table> {
+    /// Creates a table from an Arrow record batch, converting each column with `from_arrow_array`.
+    /// If a field has a q type stored in its metadata under `KDB_TYPE_KEY`, the column is converted to that type.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<Self, ConversionError> {
+        let schema = batch.schema();
+        let mut names = KBox::<List<Symbol>>::new_list();
+        let mut columns = KBox::<List<Any>>::new_list();
+        for (field, array) in schema.fields().iter().zip(batch.columns()) {
+            let kdb_type = field.metadata().get(KDB_TYPE_KEY).and_then(|t| t.bytes().next());
+            let column = from_arrow(array.as_ref(), kdb_type).map_err(|e| ConversionError::InvalidValue {
+                key: field.name().clone(),
+                source: Box::new(e),
+            })?;
+            names.push(to_symbol(field.name())?);
+            columns.push(column);
+        }
+        KBox::new_table(names, columns)
+    }
+}
+
+fn to_symbol(s: &str) -> Result<Symbol, ConversionError> {
+    Symbol::new(s).map_err(|_| ConversionError::InvalidString)
+}
+
+fn items<T>(any: &Any) -> &[T] {
+    unsafe {
+        let k = any.k_ptr();
+        slice::from_raw_parts(&(*k).union.list.g0 as *const u8 as *const T, (*k).union.list.n as usize)
+    }
+}
+
+fn items_mut<T>(any: &mut Any) -> &mut [T] {
+    unsafe {
+        let k = any.k_ptr_mut();
+        slice::from_raw_parts_mut(&mut (*k).union.list.g0 as *mut u8 as *mut T, (*k).union.list.n as usize)
+    }
+}
+
+fn new_list(t: KTypeCode, len: usize) -> KBox<Any> {
+    unsafe {
+        let mut list = KBox::<Any>::from_raw(kapi::ktn(t.into(), len as i64));
+        let data = items_mut::<u8>(&mut list).as_mut_ptr();
+        ptr::write_bytes(data, 0, len * t.list_item_size());
+        list
+    }
+}
+
+fn null_buffer<T: Copy>(values: &[T], is_null: impl Fn(T) -> bool) -> Option<NullBuffer> {
+    if values.iter().any(|v| is_null(*v)) {
+        Some(values.iter().map(|v| !is_null(*v)).collect())
+    } else {
+        None
+    }
+}
+
+/// Creates an Arrow array from a slice with the same layout as its values.
+fn copy_array<A: ArrowPrimitiveType>(values: &[A::Native], is_null: impl Fn(A::Native) -> bool) -> ArrayRef {
+    let nulls = null_buffer(values, is_null);
+    Arc::new(PrimitiveArray::<A>::new(ScalarBuffer::from(values.to_vec()), nulls))
+}
+
+/// Creates an Arrow array by mapping each non-null value. Fails if any value is out of range.
+fn map_array<A: ArrowPrimitiveType, T: Copy>(
+    values: &[T],
+    is_null: impl Fn(T) -> bool,
+    map: impl Fn(T) -> Option<A::Native>,
+) -> Result<ArrayRef, ConversionError> {
+    let nulls = null_buffer(values, &is_null);
+    let values = values
+        .iter()
+        .map(|v| {
+            if is_null(*v) {
+                Some(A::Native::default())
+            } else {
+                map(*v)
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(ConversionError::OutOfRange)?;
+    Ok(Arc::new(PrimitiveArray::<A>::new(ScalarBuffer::from(values), nulls)))
+}
+
+fn values<A: ArrowPrimitiveType>(array: &dyn Array) -> &[A::Native] {
+    array.as_primitive::<A>().values()
+}
+
+fn timestamp_values<'a>(array: &'a dyn Array, unit: &TimeUnit) -> &'a [i64] {
+    match unit {
+        TimeUnit::Second => values::<TimestampSecondType>(array),
+        TimeUnit::Millisecond => values::<TimestampMillisecondType>(array),
+        TimeUnit::Microsecond => values::<TimestampMicrosecondType>(array),
+        TimeUnit::Nanosecond => values::<TimestampNanosecondType>(array),
+    }
+}
+
+fn duration_values<'a>(array: &'a dyn Array, unit: &TimeUnit) -> &'a [i64] {
+    match unit {
+        TimeUnit::Second => values::<DurationSecondType>(array),
+        TimeUnit::Millisecond => values::<DurationMillisecondType>(array),
+        TimeUnit::Microsecond => values::<DurationMicrosecondType>(array),
+        TimeUnit::Nanosecond => values::<DurationNanosecondType>(array),
+    }
+}
+
+fn nanos_per(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1_000_000_000,
+        TimeUnit::Millisecond => 1_000_000,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    }
+}
+
+/// Creates a K list from a slice with the same layout as its items, replacing Arrow nulls with `null`.
+fn copy_values<T: Copy>(values: &[T], nulls: Option<&NullBuffer>, t: KTypeCode, null: T) -> KBox<Any> {
+    let mut list = new_list(t, values.len());
+    let items = items_mut::<T>(&mut list);
+    items.copy_from_slice(values);
+    if let Some(nulls) = nulls {
+        for i in (0..values.len()).filter(|i| nulls.is_null(*i)) {
+            items[i] = null;
+        }
+    }
+    list
+}
+
+/// Creates a K list by mapping each non-null value, replacing Arrow nulls with `null`.
+/// Fails if any value is out of range.
+fn map_values<N: Copy, T: Copy>(
+    values: &[N],
+    nulls: Option<&NullBuffer>,
+    t: KTypeCode,
+    null: T,
+    map: impl Fn(N) -> Option<T>,
+) -> Result<KBox<Any>, ConversionError> {
+    let mut list = new_list(t, values.len());
+    for (i, (item, value)) in items_mut::<T>(&mut list).iter_mut().zip(values).enumerate() {
+        *item = if nulls.map(|n| n.is_null(i)).unwrap_or(false) {
+            null
+        } else {
+            map(*value).ok_or(ConversionError::OutOfRange)?
+        };
+    }
+    Ok(list)
+}
+
+/// Creates a char list, symbol list or list of strings from the items of a string array, depending on `kdb_type`.
+fn strings<'a>(
+    items: impl ExactSizeIterator<Item = Option<&'a str>>,
+    kdb_type: Option<u8>,
+) -> Result<KBox<Any>, ConversionError> {
+    Ok(match kdb_type {
+        Some(b'c') => {
+            let mut list = new_list(CHAR_LIST, items.len());
+            for (i, (c, s)) in items_mut::<u8>(&mut list).iter_mut().zip(items).enumerate() {
+                *c = match s.map(str::as_bytes) {
+                    None | Some(b"") => b' ',
+                    Some(&[c]) => c,
+                    Some(s) => {
+                        return Err(ConversionError::at_index(
+                            i,
+                            ConversionError::InvalidLength {
+                                expected: 1,
+                                found: s.len(),
+                            },
+                        ))
+                    }
+                };
+            }
+            list
+        }
+        Some(b's') => {
+            let mut list = new_list(SYMBOL_LIST, items.len());
+            for (sym, s) in items_mut::<Symbol>(&mut list).iter_mut().zip(items) {
+                *sym = to_symbol(s.unwrap_or_default())?;
+            }
+            list
+        }
+        _ => {
+            let mut list = KBox::<List<Any>>::new_list();
+            for s in items {
+                let s = s.unwrap_or_default();
+                list.push(unsafe { KBox::from_raw(kapi::kpn(s.as_ptr() as *const i8, s.len() as i64)) });
+            }
+            list.into()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cast, list, Date, DateTime, Minute, Month, Timestamp};
+
+    fn round_trip(list: KBox<Any>) -> KBox<Any> {
+        let names = list![Symbol; symbol("a")];
+        let table = KBox::new_table(names, list![Any; list]).unwrap();
+        let batch = table.to_record_batch().unwrap();
+        let table = KBox::from_record_batch(&batch).unwrap();
+        unsafe { KBox::from_raw(kapi::r1(table.columns()[0].k_ptr() as *mut _)) }
+    }
+
+    #[test]
+    fn simple_lists_convert_to_arrow_arrays() {
+        let array = to_arrow_array(list![i64; 1, i64::MIN, 3].as_ref()).unwrap();
+        let array = array.as_primitive::<Int64Type>();
+        assert_eq!((array.value(0), array.value(2)), (1, 3));
+        assert!(array.is_null(1));
+
+        let array = to_arrow_array(list![Symbol; symbol("a"), symbol(""), symbol("a")].as_ref()).unwrap();
+        assert_eq!(
+            array.data_type(),
+            &DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+        );
+        assert_eq!(array.null_count(), 1);
+    }
+
+    #[test]
+    fn temporal_lists_are_shifted_to_the_unix_epoch() {
+        let array = to_arrow_array(list![Timestamp; Timestamp::from_raw(0)].as_ref()).unwrap();
+        assert_eq!(array.as_primitive::<TimestampNanosecondType>().value(0), K_NANO_OFFSET);
+        let array = to_arrow_array(list![Date; Date::from(-1)].as_ref()).unwrap();
+        assert_eq!(array.as_primitive::<Date32Type>().value(0), K_DAY_OFFSET - 1);
+        let array = to_arrow_array(list![Month; Month::from(-11)].as_ref()).unwrap();
+        assert_eq!(
+            array.as_primitive::<Date32Type>().value(0),
+            days_from_civil(1999, 2, 1) as i32
+        );
+    }
+
+    #[test]
+    fn tables_round_trip_through_record_batches() {
+        let l = round_trip(list![i32; 1, i32::MIN, 3].into());
+        assert_eq!(cast!(l; List<i32>).as_slice(), &[1, i32::MIN, 3]);
+        let l = round_trip(list![Month; Month::from(-13), Month::from(5)].into());
+        assert_eq!(cast!(l; List<Month>).as_slice(), &[Month::from(-13), Month::from(5)]);
+        let l = round_trip(list![Minute; Minute::from(61)].into());
+        assert_eq!(cast!(l; List<Minute>).as_slice(), &[Minute::from(61)]);
+        let l = round_trip(list![Symbol; symbol("a"), symbol("")].into());
+        assert_eq!(cast!(l; List<Symbol>).as_slice(), &[symbol("a"), symbol("")]);
+        let l = round_trip(list![i8; b'a' as i8, b'b' as i8].into());
+        assert_eq!(cast!(l; List<i8>).try_as_str().unwrap(), "ab");
+        let l = round_trip(list![DateTime; DateTime::from(1.5)].into());
+        assert_eq!(cast!(l; List<DateTime>).as_slice(), &[DateTime::from(1.5)]);
+    }
+
+    #[test]
+    fn strings_convert_to_utf8() {
+        let strings: KBox<Any> = list![Any; list![i8; b'a' as i8], list![i8; b'b' as i8, b'c' as i8]].into();
+        let array = to_arrow_array(&strings).unwrap();
+        let array = array.as_string::<i32>();
+        assert_eq!(array.value(1), "bc");
+
+        let l = from_arrow_array(array).unwrap();
+        let l = cast!(l; List<Any>);
+        assert_eq!(cast!(&l[1]; List<i8>).try_as_str().unwrap(), "bc");
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let array = PrimitiveArray::<UInt64Type>::from(vec![u64::MAX]);
+        assert!(matches!(from_arrow_array(&array), Err(ConversionError::OutOfRange)));
+    }
+}
@@ -3,6 +3,7 @@ use crate::kbox::KBox;
 use crate::symbol::Symbol;
 use crate::type_traits::*;
 use crate::{date_time_types::*, k_type::KTypeCode};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::mem;
 use std::{fmt, ptr::NonNull};
@@ -62,6 +63,20 @@ impl<T: KValue + fmt::Debug> fmt::Debug for Atom<T> {
     }
 }
 
+impl<T> PartialEq for Atom<T> {
+    fn eq(&self, other: &Atom<T>) -> bool {
+        self.k == other.k
+    }
+}
+
+impl<T> Eq for Atom<T> {}
+
+impl<T> Hash for Atom<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.k.hash(state)
+    }
+}
+
 impl<T: KValue + fmt::Display> fmt::Display for Atom<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.value())
@@ -127,7 +142,7 @@ impl<T: KValue> KBox<Atom<T>> {
 
 #[cfg(test)]
 mod tests {
-    #![allow(clippy::clippy::float_cmp)]
+    #![allow(clippy::float_cmp, clippy::bool_assert_comparison)]
 
     use super::*;
     use crate::symbol::symbol;
@@ -0,0 +1,131 @@
+//! Using byte lists with Rust I/O.
+use crate::kbox::KBox;
+use crate::list::List;
+use std::io;
+
+impl List<u8> {
+    /// Returns a reader over the bytes of the list. The cursor implements `Read`, `BufRead` and `Seek`, and reads
+    /// straight from the list without copying it.
+    ///
+    /// # Example
+    /// ```
+    /// use kdb::{KBox, List};
+    /// use std::io::Read;
+    ///
+    /// let bytes = KBox::<List<u8>>::from(&b"hello"[..]);
+    /// let mut s = String::new();
+    /// bytes.cursor().read_to_string(&mut s).unwrap();
+    /// assert_eq!(s, "hello");
+    /// ```
+    pub fn cursor(&self) -> io::Cursor<&[u8]> {
+        io::Cursor::new(self.as_slice())
+    }
+}
+
+/// Appends written bytes to the end of the list. Each write copies the whole buffer at once with `jv`, rather than
+/// pushing the bytes one at a time.
+impl io::Write for KBox<List<u8>> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl From<&List<u8>> for Vec<u8> {
+    fn from(list: &List<u8>) -> Self {
+        list.as_slice().to_vec()
+    }
+}
+
+impl From<KBox<List<u8>>> for Vec<u8> {
+    fn from(list: KBox<List<u8>>) -> Self {
+        list.as_slice().to_vec()
+    }
+}
+
+#[cfg(feature = "bytes")]
+mod bytes_support {
+    use super::*;
+    use bytes::Bytes;
+
+    impl From<&List<u8>> for Bytes {
+        fn from(list: &List<u8>) -> Self {
+            Bytes::copy_from_slice(list.as_slice())
+        }
+    }
+
+    /// Copies the list into a `Bytes` buffer. K objects can't be shared across threads, so the list can't be
+    /// used as the buffer's backing storage.
+    impl From<KBox<List<u8>>> for Bytes {
+        fn from(list: KBox<List<u8>>) -> Self {
+            Bytes::copy_from_slice(list.as_slice())
+        }
+    }
+
+    impl From<&Bytes> for KBox<List<u8>> {
+        fn from(bytes: &Bytes) -> Self {
+            KBox::from(&bytes[..])
+        }
+    }
+
+    impl From<Bytes> for KBox<List<u8>> {
+        fn from(bytes: Bytes) -> Self {
+            KBox::from(&bytes[..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{b9_serialize, d9_deserialize, list, SerializationMode};
+    use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn write_appends_to_the_list() {
+        let mut bytes = KBox::<List<u8>>::new_list();
+        bytes.write_all(b"abc").unwrap();
+        write!(bytes, "{}", 12).unwrap();
+        assert_eq!(bytes.as_slice(), b"abc12");
+        assert_eq!(Vec::from(bytes), b"abc12");
+    }
+
+    #[test]
+    fn cursor_reads_and_seeks() {
+        let bytes = KBox::<List<u8>>::from(&b"one\ntwo\n"[..]);
+        let mut cursor = bytes.cursor();
+        let mut line = String::new();
+        cursor.read_line(&mut line).unwrap();
+        assert_eq!(line, "one\n");
+        cursor.seek(SeekFrom::Start(1)).unwrap();
+        let mut rest = Vec::new();
+        cursor.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"ne\ntwo\n");
+    }
+
+    #[test]
+    fn serialized_objects_can_be_copied_through_io() {
+        let serialized = b9_serialize(SerializationMode::InProc, list![i32; 1, 2, 3]).unwrap();
+        let mut copy = KBox::<List<u8>>::new_list();
+        io::copy(&mut serialized.cursor(), &mut copy).unwrap();
+        assert_eq!(d9_deserialize(copy).unwrap(), d9_deserialize(serialized).unwrap());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn converts_to_and_from_bytes() {
+        let bytes = bytes::Bytes::from_static(b"xyz");
+        let list = KBox::<List<u8>>::from(&bytes);
+        assert_eq!(list.as_slice(), b"xyz");
+        assert_eq!(bytes::Bytes::from(list), bytes);
+    }
+}
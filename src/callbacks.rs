@@ -8,7 +8,7 @@ pub type Callback = extern "C" fn(i32) -> Option<KBox<Any>>;
 /// Equivalent to calling `sd1(fd, cb)` in the C API.
 pub fn register_callback(fd: i32, cb: Callback) -> Result<KBox<Atom<i32>>, Error> {
     unsafe {
-        let r = kapi::sd1(fd, mem::transmute(cb));
+        let r = kapi::sd1(fd, mem::transmute::<Callback, Option<kapi::KCallback>>(cb));
         if r.is_null() {
             Err(Error::Callback)
         } else {
@@ -0,0 +1,229 @@
+//! Conversions between the KDB temporal types and chrono types.
+//!
+//! Converting a KDB value into a chrono value returns an `Option`, which is `None` if the KDB value is null
+//! (or is one of the infinities, which chrono can't represent).
+use crate::date_time_types::*;
+use crate::error::ConversionError;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use std::convert::TryFrom;
+
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+fn epoch_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
+}
+
+fn epoch() -> NaiveDateTime {
+    epoch_date().and_hms_opt(0, 0, 0).unwrap()
+}
+
+fn time_from_millis(millis: i32) -> Option<NaiveTime> {
+    let millis = u32::try_from(millis).ok()?;
+    NaiveTime::from_num_seconds_from_midnight_opt(millis / 1_000, millis % 1_000 * 1_000_000)
+}
+
+fn millis_from_time(time: NaiveTime) -> i32 {
+    // Leap seconds are represented by chrono with a nanosecond value over one second.
+    (time.num_seconds_from_midnight() * 1_000 + (time.nanosecond() / 1_000_000).min(999)) as i32
+}
+
+/// Returns true for the null `0N` and the infinities `0W` and `-0W` of the 64 bit temporal types.
+fn is_null_or_infinite(nanos: i64) -> bool {
+    nanos == i64::MIN || nanos == i64::MAX || nanos == -i64::MAX
+}
+
+impl From<Timestamp> for Option<NaiveDateTime> {
+    fn from(ts: Timestamp) -> Option<NaiveDateTime> {
+        match ts.as_raw() {
+            nanos if is_null_or_infinite(nanos) => None,
+            nanos => epoch().checked_add_signed(Duration::nanoseconds(nanos)),
+        }
+    }
+}
+
+impl TryFrom<NaiveDateTime> for Timestamp {
+    type Error = ConversionError;
+
+    fn try_from(dt: NaiveDateTime) -> Result<Timestamp, ConversionError> {
+        let nanos = dt.signed_duration_since(epoch()).num_nanoseconds();
+        match nanos {
+            Some(nanos) if !is_null_or_infinite(nanos) => Ok(Timestamp::from_raw(nanos)),
+            _ => Err(ConversionError::OutOfRange),
+        }
+    }
+}
+
+impl From<Date> for Option<NaiveDate> {
+    fn from(date: Date) -> Option<NaiveDate> {
+        match date.as_raw() {
+            i32::MIN | i32::MAX => None,
+            days => epoch_date().checked_add_signed(Duration::days(days.into())),
+        }
+    }
+}
+
+impl From<NaiveDate> for Date {
+    fn from(date: NaiveDate) -> Date {
+        Date::from(date.signed_duration_since(epoch_date()).num_days() as i32)
+    }
+}
+
+impl From<DateTime> for Option<NaiveDateTime> {
+    fn from(dt: DateTime) -> Option<NaiveDateTime> {
+        let millis = (f64::from(dt) * MILLIS_PER_DAY).round();
+        if !millis.is_finite() || millis.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        epoch().checked_add_signed(Duration::try_milliseconds(millis as i64)?)
+    }
+}
+
+/// Converts to a KDB datetime, which has millisecond precision.
+impl From<NaiveDateTime> for DateTime {
+    fn from(dt: NaiveDateTime) -> DateTime {
+        DateTime::from(dt.signed_duration_since(epoch()).num_milliseconds() as f64 / MILLIS_PER_DAY)
+    }
+}
+
+/// Converts a month to the first day of that month.
+impl From<Month> for Option<NaiveDate> {
+    fn from(month: Month) -> Option<NaiveDate> {
+        match i32::from(month) {
+            i32::MIN | i32::MAX => None,
+            m => NaiveDate::from_ymd_opt(2000 + m.div_euclid(12), m.rem_euclid(12) as u32 + 1, 1),
+        }
+    }
+}
+
+/// Converts to the month containing the specified date.
+impl From<NaiveDate> for Month {
+    fn from(date: NaiveDate) -> Month {
+        Month::from((date.year() - 2000) * 12 + date.month0() as i32)
+    }
+}
+
+impl From<Time> for Option<NaiveTime> {
+    fn from(time: Time) -> Option<NaiveTime> {
+        time_from_millis(time.into())
+    }
+}
+
+/// Converts to a KDB time, which has millisecond precision.
+impl From<NaiveTime> for Time {
+    fn from(time: NaiveTime) -> Time {
+        Time::from(millis_from_time(time))
+    }
+}
+
+impl From<Second> for Option<NaiveTime> {
+    fn from(second: Second) -> Option<NaiveTime> {
+        NaiveTime::from_num_seconds_from_midnight_opt(u32::try_from(i32::from(second)).ok()?, 0)
+    }
+}
+
+/// Converts to a KDB second, truncating any fractional seconds.
+impl From<NaiveTime> for Second {
+    fn from(time: NaiveTime) -> Second {
+        Second::from(time.num_seconds_from_midnight() as i32)
+    }
+}
+
+impl From<Minute> for Option<NaiveTime> {
+    fn from(minute: Minute) -> Option<NaiveTime> {
+        let minute = u32::try_from(i32::from(minute)).ok()?;
+        NaiveTime::from_num_seconds_from_midnight_opt(minute.checked_mul(60)?, 0)
+    }
+}
+
+/// Converts to a KDB minute, truncating any seconds.
+impl From<NaiveTime> for Minute {
+    fn from(time: NaiveTime) -> Minute {
+        Minute::from((time.num_seconds_from_midnight() / 60) as i32)
+    }
+}
+
+impl From<Timespan> for Option<Duration> {
+    fn from(span: Timespan) -> Option<Duration> {
+        match i64::from(span) {
+            nanos if is_null_or_infinite(nanos) => None,
+            nanos => Some(Duration::nanoseconds(nanos)),
+        }
+    }
+}
+
+impl TryFrom<Duration> for Timespan {
+    type Error = ConversionError;
+
+    fn try_from(d: Duration) -> Result<Timespan, ConversionError> {
+        match d.num_nanoseconds() {
+            Some(nanos) if !is_null_or_infinite(nanos) => Ok(Timespan::from_nanos(nanos)),
+            _ => Err(ConversionError::DurationTooLong),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_time(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32, nano: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_nano_opt(h, min, s, nano)
+            .unwrap()
+    }
+
+    #[test]
+    fn timestamps_convert_to_and_from_naive_date_times() {
+        let dt = date_time(1969, 7, 20, 20, 17, 40, 123_456_789);
+        let ts = Timestamp::try_from(dt).unwrap();
+        assert_eq!(Option::<NaiveDateTime>::from(ts), Some(dt));
+        assert_eq!(Option::<NaiveDateTime>::from(Timestamp::from_raw(i64::MIN)), None);
+        assert_eq!(Option::<NaiveDateTime>::from(Timestamp::from_raw(i64::MAX)), None);
+        assert_eq!(Option::<NaiveDateTime>::from(Timestamp::from_raw(-i64::MAX)), None);
+        assert!(Timestamp::try_from(date_time(1000, 1, 1, 0, 0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn dates_and_months_convert_to_and_from_naive_dates() {
+        let date = NaiveDate::from_ymd_opt(1812, 6, 24).unwrap();
+        assert_eq!(Option::<NaiveDate>::from(Date::from(date)), Some(date));
+        assert_eq!(Option::<NaiveDate>::from(Date::from(i32::MIN)), None);
+        assert_eq!(Option::<NaiveDate>::from(Date::from(-i32::MAX)), None);
+
+        let month = Month::from(date);
+        assert_eq!(i32::from(month), (1812 - 2000) * 12 + 5);
+        assert_eq!(Option::<NaiveDate>::from(month), NaiveDate::from_ymd_opt(1812, 6, 1));
+        assert_eq!(Option::<NaiveDate>::from(Month::from(i32::MIN)), None);
+    }
+
+    #[test]
+    fn date_times_convert_with_millisecond_precision() {
+        let dt = date_time(1990, 3, 4, 5, 6, 7, 890_000_000);
+        assert_eq!(Option::<NaiveDateTime>::from(DateTime::from(dt)), Some(dt));
+        assert_eq!(Option::<NaiveDateTime>::from(DateTime::from(f64::NAN)), None);
+    }
+
+    #[test]
+    fn times_convert_to_and_from_naive_times() {
+        let t = NaiveTime::from_hms_milli_opt(13, 14, 15, 161).unwrap();
+        assert_eq!(Option::<NaiveTime>::from(Time::from(t)), Some(t));
+        assert_eq!(i32::from(Second::from(t)), 13 * 3600 + 14 * 60 + 15);
+        assert_eq!(i32::from(Minute::from(t)), 13 * 60 + 14);
+        assert_eq!(
+            Option::<NaiveTime>::from(Minute::from(61)),
+            NaiveTime::from_hms_opt(1, 1, 0)
+        );
+        assert_eq!(Option::<NaiveTime>::from(Second::from(i32::MIN)), None);
+        assert_eq!(Option::<NaiveTime>::from(Time::from(i32::MIN)), None);
+    }
+
+    #[test]
+    fn timespans_convert_to_and_from_durations() {
+        let d = Duration::nanoseconds(-1_234_567_890);
+        assert_eq!(Option::<Duration>::from(Timespan::try_from(d).unwrap()), Some(d));
+        assert_eq!(Option::<Duration>::from(Timespan::from_nanos(i64::MIN)), None);
+        assert_eq!(Option::<Duration>::from(Timespan::from_nanos(-i64::MAX)), None);
+        assert!(Timespan::try_from(Duration::days(1_000_000)).is_err());
+    }
+}
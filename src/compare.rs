@@ -0,0 +1,270 @@
+//! Ordering of K values, following the same rules as sorting in q.
+use crate::any::Any;
+use crate::atom::Atom;
+use crate::dictionary::{Dict, Dictionary};
+use crate::k::{Attr, K};
+use crate::k_type::*;
+use crate::kbox::KBox;
+use crate::list::List;
+use crate::table::Table;
+use crate::type_traits::{KListable, KObject};
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::ptr;
+
+/// Compares two K values in the same order that q's `asc` and `iasc` sort them.
+///
+/// Values of different types are ordered by their type code. Atoms of the same type are ordered by value, with
+/// nulls first. Symbols are ordered lexicographically, rather than by their address. Lists are ordered item by item,
+/// and then by length. Dictionaries are ordered by their keys and then their values, and tables by their columns.
+///
+/// # Example
+/// ```
+/// use kdb::{compare, parse_literal};
+/// use std::cmp::Ordering;
+///
+/// let a = parse_literal("`apple").unwrap();
+/// let b = parse_literal("`banana").unwrap();
+/// assert_eq!(compare(&a, &b), Ordering::Less);
+/// assert_eq!(compare(&parse_literal("0N").unwrap(), &parse_literal("-5").unwrap()), Ordering::Less);
+/// ```
+pub fn compare(a: &Any, b: &Any) -> Ordering {
+    a.cmp(b)
+}
+
+fn compare_floats(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.partial_cmp(&b).unwrap(),
+    }
+}
+
+unsafe fn read<T: Copy>(p: *const u8) -> T {
+    ptr::read_unaligned(p as *const T)
+}
+
+/// Compares two items of a simple list (or two atoms) of type `t`, given pointers to their values.
+unsafe fn compare_items(t: KTypeCode, a: *const u8, b: *const u8) -> Ordering {
+    match t {
+        BOOLEAN_LIST | BYTE_LIST | CHAR_LIST => (*a).cmp(&*b),
+        SHORT_LIST => read::<i16>(a).cmp(&read(b)),
+        INT_LIST | MONTH_LIST | DATE_LIST | MINUTE_LIST | SECOND_LIST | TIME_LIST => read::<i32>(a).cmp(&read(b)),
+        LONG_LIST | TIMESTAMP_LIST | TIMESPAN_LIST => read::<i64>(a).cmp(&read(b)),
+        REAL_LIST => compare_floats(read::<f32>(a).into(), read::<f32>(b).into()),
+        FLOAT_LIST | DATE_TIME_LIST => compare_floats(read(a), read(b)),
+        SYMBOL_LIST => {
+            let (a, b) = (read::<*const i8>(a), read::<*const i8>(b));
+            CStr::from_ptr(a).to_bytes().cmp(CStr::from_ptr(b).to_bytes())
+        }
+        GUID_LIST => std::slice::from_raw_parts(a, 16).cmp(std::slice::from_raw_parts(b, 16)),
+        MIXED_LIST => read::<&K>(a).cmp(read::<&K>(b)),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Compares items `i` and `j` of a simple or mixed list.
+unsafe fn compare_at(k: &K, i: usize, j: usize) -> Ordering {
+    let size = k.t.list_item_size();
+    let data = k.union.list.g0.as_ptr();
+    compare_items(k.t, data.add(i * size), data.add(j * size))
+}
+
+/// Compares item `i` of a simple list with an atom of the same type, without copying the item into an atom.
+pub(crate) unsafe fn compare_item_with_atom(list: &K, i: usize, atom: &K) -> Ordering {
+    compare_items(list.t, list.item_bytes(i).as_ptr(), atom.atom_bytes().as_ptr())
+}
+
+impl PartialOrd for K {
+    fn partial_cmp(&self, other: &K) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders K values in the same way as q's sort. This is consistent with structural equality.
+impl Ord for K {
+    fn cmp(&self, other: &K) -> Ordering {
+        if self.t != other.t {
+            return i32::from(self.t).cmp(&i32::from(other.t));
+        }
+        unsafe {
+            match self.t {
+                t if i32::from(t) < 0 && t != ERROR => {
+                    compare_items(t.as_list(), self.atom_bytes().as_ptr(), other.atom_bytes().as_ptr())
+                }
+                t if t == MIXED_LIST || t.is_simple_list() => {
+                    let (n, m) = (self.union.list.n as usize, other.union.list.n as usize);
+                    let size = t.list_item_size();
+                    let (a, b) = (self.union.list.g0.as_ptr(), other.union.list.g0.as_ptr());
+                    (0..n.min(m))
+                        .map(|i| compare_items(t, a.add(i * size), b.add(i * size)))
+                        .find(|o| *o != Ordering::Equal)
+                        .unwrap_or_else(|| n.cmp(&m))
+                }
+                DICT => (*self.union.dict.k)
+                    .cmp(&*other.union.dict.k)
+                    .then_with(|| (*self.union.dict.v).cmp(&*other.union.dict.v)),
+                TABLE => (*self.union.k0).cmp(&*other.union.k0),
+                ERROR => CStr::from_ptr(self.union.s).cmp(CStr::from_ptr(other.union.s)),
+                GENERIC_NULL => Ordering::Equal,
+                _ => (self as *const K).cmp(&(other as *const K)),
+            }
+        }
+    }
+}
+
+macro_rules! impl_ord {
+    ($($t:ty),*) => {
+        $(
+            impl PartialOrd for $t {
+                fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+
+            impl Ord for $t {
+                fn cmp(&self, other: &Self) -> Ordering {
+                    unsafe { (*self.k_ptr()).cmp(&*other.k_ptr()) }
+                }
+            }
+        )*
+    };
+}
+
+impl_ord!(Any, Dictionary, Table);
+
+impl<T> PartialOrd for List<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for List<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        unsafe { (*self.k_ptr()).cmp(&*other.k_ptr()) }
+    }
+}
+
+impl<Key, Value> PartialOrd for Dict<Key, Value> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Key, Value> Ord for Dict<Key, Value> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        unsafe { (*self.k_ptr()).cmp(&*other.k_ptr()) }
+    }
+}
+
+impl<T> PartialOrd for Atom<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Atom<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        unsafe { (*self.k_ptr()).cmp(&*other.k_ptr()) }
+    }
+}
+
+impl<T: KListable> List<T> {
+    /// Returns the indices that would sort the list into ascending order, like q's `iasc`.
+    /// Equal items keep their original order.
+    pub fn grade(&self) -> KBox<List<i64>> {
+        self.grade_indices().into_iter().map(|i| i as i64).collect()
+    }
+
+    /// Sorts the list into ascending order, in the same order as q's `asc`. Simple lists are given the sorted
+    /// attribute (`s#`).
+    pub fn sort(&mut self) {
+        let order = self.grade_indices();
+        unsafe {
+            let k = &mut *self.k_ptr_mut();
+            let size = k.t.list_item_size();
+            let bytes = k.list_bytes().to_vec();
+            let data = k.union.list.g0.as_mut_ptr();
+            for (dest, src) in order.into_iter().enumerate() {
+                ptr::copy_nonoverlapping(bytes.as_ptr().add(src * size), data.add(dest * size), size);
+            }
+            if k.t.is_simple_list() {
+                k.set_attr(Attr::SORTED);
+            }
+        }
+    }
+
+    /// Returns true if the list has the sorted attribute (`s#`).
+    pub fn is_sorted_attr(&self) -> bool {
+        unsafe { (*self.k_ptr()).attr().sorted() }
+    }
+
+    fn grade_indices(&self) -> Vec<usize> {
+        let k = unsafe { &*self.k_ptr() };
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by(|&i, &j| unsafe { compare_at(k, i, j) });
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::literal::literal;
+    use crate::{list, symbol, Symbol};
+
+    #[test]
+    fn atoms_are_ordered_with_nulls_first() {
+        assert_eq!(compare(&literal("0N"), &literal("-0W")), Ordering::Less);
+        assert_eq!(compare(&literal("0n"), &literal("-0w")), Ordering::Less);
+        assert_eq!(compare(&literal("1.5"), &literal("2f")), Ordering::Less);
+        assert_eq!(
+            compare(&literal("2020.01.02"), &literal("2020.01.01")),
+            Ordering::Greater
+        );
+        assert_eq!(compare(&literal("`"), &literal("`a")), Ordering::Less);
+        assert_eq!(compare(&literal("`b"), &literal("`ab")), Ordering::Greater);
+    }
+
+    #[test]
+    fn different_types_are_ordered_by_type_code() {
+        assert_eq!(compare(&literal("1 2"), &literal("1 2i")), Ordering::Greater);
+        assert_eq!(compare(&literal("1"), &literal("1 2")), Ordering::Less);
+    }
+
+    #[test]
+    fn lists_are_ordered_item_by_item() {
+        assert_eq!(compare(&literal("1 2 3"), &literal("1 3")), Ordering::Less);
+        assert_eq!(compare(&literal("1 2"), &literal("1 2 3")), Ordering::Less);
+        assert_eq!(compare(&literal("(1;`b)"), &literal("(1;`a)")), Ordering::Greater);
+        assert_eq!(compare(&literal("`a`b!1 2"), &literal("`a`b!1 2")), Ordering::Equal);
+    }
+
+    #[test]
+    fn sort_orders_lists_and_sets_the_sorted_attribute() {
+        let mut l = list![Symbol; symbol("c"), symbol(""), symbol("ab"), symbol("a")];
+        assert_eq!(l.grade().as_slice(), &[1, 3, 2, 0]);
+        l.sort();
+        assert_eq!(l.as_slice(), &[symbol(""), symbol("a"), symbol("ab"), symbol("c")]);
+        assert!(l.is_sorted_attr());
+
+        let mut l = list![f64; 3.0, f64::NAN, -1.0, f64::NEG_INFINITY];
+        l.sort();
+        assert!(l[0].is_nan());
+        assert_eq!(&l[1..], &[f64::NEG_INFINITY, -1.0, 3.0]);
+
+        let mut l = list![Any; 2, symbol("a"), 1];
+        l.sort();
+        assert_eq!(l.as_slice(), &[KBox::from(symbol("a")), KBox::from(1), KBox::from(2)]);
+        assert!(!l.is_sorted_attr());
+    }
+
+    #[test]
+    fn mutating_a_list_clears_the_sorted_attribute() {
+        let mut l = list![i64; 3, 1, 2];
+        l.sort();
+        l[0] = 10;
+        assert!(!l.is_sorted_attr());
+    }
+}
@@ -10,6 +10,7 @@ use crate::k_error::KError;
 use crate::k_type::ERROR;
 use crate::kapi;
 use crate::kbox::KBox;
+use crate::list::normalize;
 
 use std::ffi::CString;
 use std::ptr;
@@ -20,12 +21,12 @@ fn null() -> *const K {
 }
 
 macro_rules! evaluate {
-    ($conn: expr, $func: expr $(, $param:expr)*,) => {
-        evaluate!($conn, $func $(, $expr)*);
+    ($self: expr, $conn: expr, $func: expr $(, $param:expr)*,) => {
+        evaluate!($self, $conn, $func $(, $param)*)
     };
-    ($conn: expr, $func: expr $(, $param:expr)*) => {
+    ($self: expr, $conn: expr, $func: expr $(, $param:expr)*) => {
         {
-            let result = unsafe { kapi::k($conn, CString::new($func).unwrap().as_ptr() $(, $param.into_raw() as *const K)*, null()) };
+            let result = unsafe { kapi::k($conn, CString::new($func).unwrap().as_ptr() $(, $self.prepare($param).into_raw() as *const K)*, null()) };
             if result.is_null() {
                 Err(Error::NetworkError)
             } else if $conn > 0 && unsafe { (*result).t == ERROR } {
@@ -44,7 +45,10 @@ fn from_raw(k: *mut K) -> KBox<Any> {
 
 /// Represents a connection to a remote or embedded KDB instance,
 /// which can be used to send and query data on that instance.
-pub struct Connection(i32);
+pub struct Connection {
+    handle: i32,
+    normalize_args: bool,
+}
 
 impl Connection {
     /// [non-embedded only] Connect to a remote instance of KDB.
@@ -68,14 +72,38 @@ impl Connection {
             0 => Err(ConnectionError::BadCredentials),
             -1 => Err(ConnectionError::CouldNotConnect),
             -2 => Err(ConnectionError::Timeout),
-            x => Ok(Self(x)),
+            x => Ok(Self::from_handle(x)),
         }
     }
 
     /// [embedded only] Connect to an embedded KDB instance.
     #[cfg(any(feature = "embedded", doc))]
     pub fn new() -> Self {
-        Connection(0)
+        Connection::from_handle(0)
+    }
+
+    fn from_handle(handle: i32) -> Self {
+        Connection {
+            handle,
+            normalize_args: false,
+        }
+    }
+
+    /// Sets whether arguments are normalized before they are sent. When enabled, any general lists of atoms that
+    /// all have the same type are collapsed into simple lists, as q would do, including lists nested in general
+    /// lists, dictionaries and tables. This is off by default.
+    ///
+    /// Lists built in Rust, for example with `list![Any; ...]`, otherwise arrive in q as general lists (type 0).
+    pub fn set_normalize_args(&mut self, normalize: bool) {
+        self.normalize_args = normalize;
+    }
+
+    fn prepare(&self, arg: KBox<Any>) -> KBox<Any> {
+        if self.normalize_args {
+            normalize(arg)
+        } else {
+            arg
+        }
     }
 
     /// [non-embedded only] Publish a value asynchronously to KDB.
@@ -88,17 +116,17 @@ impl Connection {
     ) -> Result<(), Error> {
         // Note that when sending asynchronously, we shouldn't call r0 on the return value - it's
         // not an owned K type.
-        evaluate!(-self.0, callback, topic.into(), object.into()).map(|_| ())
+        evaluate!(self, -self.handle, callback, topic.into(), object.into()).map(|_| ())
     }
 
     /// Evaluate a q expression with no parameters and return a result.
     pub fn eval(&self, query: &str) -> Result<KBox<Any>, Error> {
-        evaluate!(self.0, query).map(from_raw)
+        evaluate!(self, self.handle, query).map(from_raw)
     }
 
     /// Evaluate a q function with a single parameter and return the result.
     pub fn eval_1(&self, function: &str, param: impl Into<KBox<Any>>) -> Result<KBox<Any>, Error> {
-        evaluate!(self.0, function, param.into()).map(from_raw)
+        evaluate!(self, self.handle, function, param.into()).map(from_raw)
     }
 
     /// Evaluate a q function with two parameters and return the result.
@@ -108,7 +136,7 @@ impl Connection {
         param: impl Into<KBox<Any>>,
         param_2: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
-        evaluate!(self.0, function, param.into(), param_2.into()).map(from_raw)
+        evaluate!(self, self.handle, function, param.into(), param_2.into()).map(from_raw)
     }
 
     /// Evaluate a q function with three parameters and return the result.
@@ -119,7 +147,15 @@ impl Connection {
         param_2: impl Into<KBox<Any>>,
         param_3: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
-        evaluate!(self.0, function, param.into(), param_2.into(), param_3.into()).map(from_raw)
+        evaluate!(
+            self,
+            self.handle,
+            function,
+            param.into(),
+            param_2.into(),
+            param_3.into()
+        )
+        .map(from_raw)
     }
 
     /// Evaluate a q function with four parameters and return the result.
@@ -132,7 +168,8 @@ impl Connection {
         param_4: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
         evaluate!(
-            self.0,
+            self,
+            self.handle,
             function,
             param.into(),
             param_2.into(),
@@ -153,7 +190,8 @@ impl Connection {
         param_5: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
         evaluate!(
-            self.0,
+            self,
+            self.handle,
             function,
             param.into(),
             param_2.into(),
@@ -165,7 +203,7 @@ impl Connection {
     }
 
     /// Evaluate a q function with six parameters and return the result.
-    #[allow(clippy::clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn eval_6(
         &self,
         function: &str,
@@ -177,7 +215,8 @@ impl Connection {
         param_6: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
         evaluate!(
-            self.0,
+            self,
+            self.handle,
             function,
             param.into(),
             param_2.into(),
@@ -190,7 +229,7 @@ impl Connection {
     }
 
     /// Evaluate a q function with seven parameters and return the result.
-    #[allow(clippy::clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn eval_7(
         &self,
         function: &str,
@@ -203,7 +242,8 @@ impl Connection {
         param_7: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
         evaluate!(
-            self.0,
+            self,
+            self.handle,
             function,
             param.into(),
             param_2.into(),
@@ -217,7 +257,7 @@ impl Connection {
     }
 
     /// See above and add one parameter.
-    #[allow(clippy::clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn eval_8(
         &self,
         function: &str,
@@ -231,7 +271,8 @@ impl Connection {
         param_8: impl Into<KBox<Any>>,
     ) -> Result<KBox<Any>, Error> {
         evaluate!(
-            self.0,
+            self,
+            self.handle,
             function,
             param.into(),
             param_2.into(),
@@ -250,7 +291,7 @@ impl Drop for Connection {
     #[cfg(not(feature = "embedded"))]
     fn drop(&mut self) {
         unsafe {
-            kapi::kclose(self.0);
+            kapi::kclose(self.handle);
         }
     }
     #[cfg(feature = "embedded")]
@@ -260,6 +301,6 @@ impl Drop for Connection {
 #[cfg(feature = "embedded")]
 impl Default for Connection {
     fn default() -> Self {
-        Connection(0)
+        Connection::from_handle(0)
     }
 }
@@ -0,0 +1,267 @@
+//! Rendering K values the way the q console displays them.
+use crate::any::Any;
+use crate::dictionary::{Dict, Dictionary};
+use crate::k_type::*;
+use crate::list::simple_item_as_atom;
+use crate::q_string::{bare_atom, to_q_string};
+use crate::table::Table;
+use crate::type_traits::KObject;
+use std::fmt::{self, Write};
+
+/// The console height and width used by `{:#}`, which is q's default `\c 25 80`.
+const DEFAULT_CONSOLE_SIZE: (usize, usize) = (25, 80);
+
+impl Any {
+    /// Writes the value the way the q console displays it. Tables, keyed tables and dictionaries are laid out
+    /// in rows, and the output is limited to `height` lines of at most `width` characters, like `\c`.
+    /// Anything cut off is replaced by `..`.
+    pub fn fmt_console(&self, w: &mut dyn Write, height: usize, width: usize) -> fmt::Result {
+        let mut lines = lines(self, height);
+        if lines.len() > height {
+            lines.truncate(height.saturating_sub(1));
+            lines.push("..".to_owned());
+        }
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                w.write_char('\n')?;
+            }
+            if line.chars().count() > width {
+                let kept: String = line.chars().take(width.saturating_sub(2)).collect();
+                write!(w, "{}..", kept)?;
+            } else {
+                w.write_str(line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `{}` writes the value as q source text, in the same format as `-3!`. The alternate form `{:#}` lays
+/// the value out like the q console, with a height and width of 25 and 80.
+///
+/// # Example
+/// ```
+/// use kdb::parse_literal;
+///
+/// let t = parse_literal("([] a:1 2; b:`x`y)").unwrap();
+/// assert_eq!(format!("{}", t), "+`a`b!(1 2;`x`y)");
+/// assert_eq!(format!("{:#}", t), "a b\n---\n1 x\n2 y");
+/// ```
+impl fmt::Display for Any {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let (height, width) = DEFAULT_CONSOLE_SIZE;
+            self.fmt_console(f, height, width)
+        } else {
+            self.fmt_q(f, None)
+        }
+    }
+}
+
+impl fmt::Display for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(AsRef::<Any>::as_ref(self), f)
+    }
+}
+
+impl<Key, Value> fmt::Display for Dict<Key, Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(AsRef::<Any>::as_ref(self), f)
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(AsRef::<Any>::as_ref(self), f)
+    }
+}
+
+/// Lays out a value as lines of text. At most `max_rows` rows of a table or dictionary are rendered.
+fn lines(any: &Any, max_rows: usize) -> Vec<String> {
+    match any.k_type() {
+        TABLE => table_lines(None, unsafe { &*(any as *const Any as *const Table) }, max_rows),
+        DICT => {
+            let (keys, values) = unsafe {
+                let dict = (*any.k_ptr()).union.dict;
+                (&*(dict.k as *const Any), &*(dict.v as *const Any))
+            };
+            match (keys.k_type(), values.k_type()) {
+                (TABLE, TABLE) => unsafe {
+                    table_lines(
+                        Some(&*(keys as *const Any as *const Table)),
+                        &*(values as *const Any as *const Table),
+                        max_rows,
+                    )
+                },
+                _ => dict_lines(keys, values, max_rows),
+            }
+        }
+        _ => vec![to_q_string(any)],
+    }
+}
+
+/// The text shown for item `i` of a list.
+fn cell_at(list: &Any, i: usize, quote_strings: bool) -> String {
+    if list.k_type() == MIXED_LIST {
+        let items = unsafe { &*(list as *const Any as *const crate::List<Any>) };
+        cell(&items[i], quote_strings)
+    } else {
+        let atom = unsafe { simple_item_as_atom(list.k_ptr(), i) };
+        cell(&atom, quote_strings)
+    }
+}
+
+/// The text shown for a value in a table or dictionary. Atoms are shown without their type suffixes, and nulls
+/// are left blank.
+fn cell(any: &Any, quote_strings: bool) -> String {
+    let k = unsafe { &*any.k_ptr() };
+    match k.t {
+        t if i32::from(t) < 0 && t != ERROR && any.is_null() => String::new(),
+        SYMBOL_ATOM => to_q_string(any)[1..].to_owned(),
+        BOOLEAN_ATOM => if unsafe { k.union.g } != 0 { "1" } else { "0" }.to_owned(),
+        BYTE_ATOM => format!("{:02x}", unsafe { k.union.g }),
+        CHAR_ATOM if !quote_strings => (unsafe { k.union.g } as char).to_string(),
+        CHAR_LIST if !quote_strings => {
+            let list = unsafe { &*(any as *const Any as *const crate::List<i8>) };
+            let bytes: Vec<u8> = list.iter().map(|c| *c as u8).collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        _ => bare_atom(any).unwrap_or_else(|| to_q_string(any)),
+    }
+}
+
+/// The header and cells of each column in a table, rendering at most `max_rows` rows.
+fn column_cells(table: &Table, max_rows: usize) -> Vec<Vec<String>> {
+    let rows = table.len().min(max_rows);
+    table
+        .column_names()
+        .iter()
+        .zip(table.columns().iter())
+        .map(|(name, column)| {
+            let mut cells = vec![name.to_string()];
+            cells.extend((0..rows).map(|i| cell_at(column, i, false)));
+            cells
+        })
+        .collect()
+}
+
+/// Joins the cells of a row, padding each one to the width of its column.
+fn join_row(columns: &[Vec<String>], widths: &[usize], row: usize) -> String {
+    let mut line = String::new();
+    for (i, (column, width)) in columns.iter().zip(widths).enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        write!(line, "{:width$}", column[row], width = width).unwrap();
+    }
+    line
+}
+
+fn widths(columns: &[Vec<String>]) -> Vec<usize> {
+    columns
+        .iter()
+        .map(|c| c.iter().map(|s| s.chars().count()).max().unwrap_or(0))
+        .collect()
+}
+
+/// The total width of a set of columns, including the spaces between them.
+fn total_width(widths: &[usize]) -> usize {
+    widths.iter().sum::<usize>() + widths.len().saturating_sub(1)
+}
+
+fn table_lines(keys: Option<&Table>, values: &Table, max_rows: usize) -> Vec<String> {
+    let value_columns = column_cells(values, max_rows);
+    let value_widths = widths(&value_columns);
+    let key_columns = keys.map(|k| column_cells(k, max_rows)).unwrap_or_default();
+    let key_widths = widths(&key_columns);
+
+    let row = |i: usize| {
+        let values = join_row(&value_columns, &value_widths, i);
+        let line = if keys.is_some() {
+            format!("{}| {}", join_row(&key_columns, &key_widths, i), values)
+        } else {
+            values
+        };
+        line.trim_end().to_owned()
+    };
+    let mut lines = vec![row(0)];
+    let dashes = "-".repeat(total_width(&value_widths));
+    lines.push(match keys {
+        Some(_) => format!("{}| {}", "-".repeat(total_width(&key_widths)), dashes),
+        None => dashes,
+    });
+    let rows = value_columns.first().map(|c| c.len() - 1).unwrap_or(0);
+    lines.extend((1..=rows).map(row));
+    if values.len() > max_rows {
+        lines.push("..".to_owned());
+    }
+    lines
+}
+
+fn dict_lines(keys: &Any, values: &Any, max_rows: usize) -> Vec<String> {
+    let (len, values_len) = match (keys.list_len(), values.list_len()) {
+        (Some(k), Some(v)) => (k, v),
+        _ => return vec![format!("{}!{}", to_q_string(keys), to_q_string(values))],
+    };
+    let rows = len.min(values_len).min(max_rows);
+    let keys: Vec<String> = (0..rows).map(|i| cell_at(keys, i, false)).collect();
+    let width = keys.iter().map(|k| k.chars().count()).max().unwrap_or(0);
+    let mut lines: Vec<String> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| format!("{:width$}| {}", key, cell_at(values, i, true), width = width))
+        .collect();
+    if len > max_rows {
+        lines.push("..".to_owned());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_literal;
+
+    fn console(s: &str, height: usize, width: usize) -> String {
+        let mut out = String::new();
+        parse_literal(s).unwrap().fmt_console(&mut out, height, width).unwrap();
+        out
+    }
+
+    #[test]
+    fn lays_out_tables() {
+        let t = "([] sym:`a`bb`c; price:1.5 0n 3f; size:10 20 30h)";
+        assert_eq!(
+            console(t, 25, 80),
+            "sym price size\n--------------\na   1.5   10\nbb        20\nc   3     30"
+        );
+    }
+
+    #[test]
+    fn lays_out_keyed_tables() {
+        let t = "([k:`x`yy] v:1 2; s:(\"ab\";\"c\"))";
+        assert_eq!(console(t, 25, 80), "k | v s\n--| ----\nx | 1 ab\nyy| 2 c");
+    }
+
+    #[test]
+    fn lays_out_dictionaries() {
+        let d = "`a`bcd!(1;\"xy\")";
+        assert_eq!(console(d, 25, 80), "a  | 1\nbcd| \"xy\"");
+    }
+
+    #[test]
+    fn truncates_to_height_and_width() {
+        assert_eq!(console("([] a:1 2 3 4 5)", 5, 80), "a\n-\n1\n2\n..");
+        assert_eq!(console("`a`b`c`d!1 2 3 4", 3, 80), "a| 1\nb| 2\n..");
+        assert_eq!(console("1 2 3 4 5 6", 25, 8), "1 2 3 ..");
+    }
+
+    #[test]
+    fn alternate_display_uses_console_layout() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        assert_eq!(format!("{}", d), "`a`b!1 2");
+        assert_eq!(format!("{:#}", d), "a| 1\nb| 2");
+        let d = crate::cast!(d; Dictionary);
+        assert_eq!(format!("{:#}", d), "a| 1\nb| 2");
+    }
+}
@@ -0,0 +1,596 @@
+//! Conversions between Rust values and K objects.
+//!
+//! `IntoKdb` and `FromKdb` are implemented for all the primitive types that can be stored in
+//! an atom, for strings, `Option` and `Vec`. With the `derive` feature enabled, they can be derived
+//! for structs (which map to symbol keyed dictionaries) and enums (which map to symbols, or tagged dictionaries
+//! for variants that carry data). `FromKdb` is also implemented for boxed K objects, and general lists can be
+//! destructured into tuples of up to 12 `FromKdb` values with `TryFrom`.
+//!
+//! # Example
+//! ```
+//! use kdb::{FromKdb, IntoKdb};
+//!
+//! let k = vec![1i32, 2, 3].into_kdb();
+//! assert_eq!(Vec::<i32>::from_kdb(&k).unwrap(), vec![1, 2, 3]);
+//! ```
+use crate::any::{generic_null, Any};
+use crate::atom::Atom;
+use crate::dictionary::Dictionary;
+use crate::error::ConversionError;
+use crate::k::K;
+use crate::k_type::{MIXED_LIST, SYMBOL_ATOM, SYMBOL_LIST};
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::krc::KRc;
+use crate::list::{simple_item_as_atom, List};
+use crate::symbol::Symbol;
+use crate::try_cast;
+use crate::type_traits::{KListable, KObject, KTyped, KValue};
+use std::convert::TryFrom;
+
+/// A type that can be converted into a K object.
+///
+/// This can be derived for structs and enums using the `derive` feature.
+pub trait IntoKdb {
+    /// Converts the value into a K object.
+    fn into_kdb(self) -> KBox<Any>;
+
+    /// Converts a vector of values into a K list. By default this creates a mixed list,
+    /// types that can be stored in a simple list override this to create one.
+    #[doc(hidden)]
+    fn vec_into_kdb(items: Vec<Self>) -> KBox<Any>
+    where
+        Self: Sized,
+    {
+        items
+            .into_iter()
+            .map(IntoKdb::into_kdb)
+            .collect::<KBox<List<Any>>>()
+            .into()
+    }
+}
+
+/// A type that can be created from a K object.
+///
+/// This can be derived for structs and enums using the `derive` feature.
+pub trait FromKdb: Sized {
+    /// Attempts to convert the K object into a Rust value.
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError>;
+
+    /// The value to use when a dictionary key for this type is missing.
+    /// Returns `None` if the key is required.
+    #[doc(hidden)]
+    fn from_missing() -> Option<Self> {
+        None
+    }
+
+    /// Converts a K list into a vector of values. By default this expects a mixed list,
+    /// types that can be stored in a simple list override this to read one.
+    #[doc(hidden)]
+    fn vec_from_kdb(any: &Any) -> Result<Vec<Self>, ConversionError> {
+        try_cast!(any; List<Any>)?
+            .iter()
+            .enumerate()
+            .map(|(i, item)| Self::from_kdb(item).map_err(|e| ConversionError::at_index(i, e)))
+            .collect()
+    }
+}
+
+impl<T: KValue + KListable<ListItem = T> + Copy> IntoKdb for T {
+    fn into_kdb(self) -> KBox<Any> {
+        KBox::new_atom(self).into()
+    }
+
+    fn vec_into_kdb(items: Vec<Self>) -> KBox<Any> {
+        items.into_iter().collect::<KBox<List<T>>>().into()
+    }
+}
+
+impl<T: KValue + KListable<ListItem = T> + Copy> FromKdb for T {
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError> {
+        Ok(try_cast!(any; Atom<T>)?.value())
+    }
+
+    fn vec_from_kdb(any: &Any) -> Result<Vec<Self>, ConversionError> {
+        if any.k_type() == MIXED_LIST {
+            try_cast!(any; List<Any>)?
+                .iter()
+                .enumerate()
+                .map(|(i, item)| Self::from_kdb(item).map_err(|e| ConversionError::at_index(i, e)))
+                .collect()
+        } else {
+            Ok(try_cast!(any; List<T>)?.as_slice().to_vec())
+        }
+    }
+}
+
+impl IntoKdb for &str {
+    fn into_kdb(self) -> KBox<Any> {
+        KBox::<List<i8>>::new_string(self).into()
+    }
+}
+
+impl IntoKdb for String {
+    fn into_kdb(self) -> KBox<Any> {
+        self.as_str().into_kdb()
+    }
+}
+
+impl FromKdb for String {
+    /// Strings can be read from either a char list or a symbol atom.
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError> {
+        if any.k_type() == SYMBOL_ATOM {
+            Ok(try_cast!(any; Atom<Symbol>)?.value().try_as_str()?.to_owned())
+        } else {
+            Ok(try_cast!(any; List<i8>)?.try_as_str()?.to_owned())
+        }
+    }
+}
+
+impl<T: IntoKdb> IntoKdb for Option<T> {
+    /// `None` is converted to the generic null `(::)`.
+    fn into_kdb(self) -> KBox<Any> {
+        match self {
+            Some(value) => value.into_kdb(),
+            None => generic_null(),
+        }
+    }
+}
+
+impl<T: FromKdb> FromKdb for Option<T> {
+    /// The generic null, and null atoms, are converted to `None`.
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError> {
+        if any.is_null() {
+            Ok(None)
+        } else {
+            T::from_kdb(any).map(Some)
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: IntoKdb> IntoKdb for Vec<T> {
+    fn into_kdb(self) -> KBox<Any> {
+        T::vec_into_kdb(self)
+    }
+}
+
+impl<T: FromKdb> FromKdb for Vec<T> {
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError> {
+        T::vec_from_kdb(any)
+    }
+}
+
+impl<T: KObject> IntoKdb for KBox<T>
+where
+    KBox<T>: Into<KBox<Any>>,
+{
+    fn into_kdb(self) -> KBox<Any> {
+        self.into()
+    }
+}
+
+/// The object is moved out of the `KRc` if it isn't shared, and copied if it is, so the result can be modified
+/// without affecting other references. Objects that can't be copied are shared instead.
+impl<T: KObject> IntoKdb for KRc<T>
+where
+    KBox<T>: Into<KBox<Any>>,
+{
+    fn into_kdb(self) -> KBox<Any> {
+        match KRc::unwrap_or_copy(self) {
+            Ok(value) => value.into(),
+            Err(shared) => unsafe { KBox::from_raw(kapi::r1(shared.k_ptr() as *mut K)) },
+        }
+    }
+}
+
+/// K objects are converted by incrementing their reference count, rather than copying them. The result is a
+/// `KRc` because the object is still shared with the list it came from; use `KRc::unwrap_or_copy` to get a `KBox`.
+impl<T: KObject + KTyped> FromKdb for KRc<T> {
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError> {
+        T::check_type(unsafe { &*any.k_ptr() })?;
+        Ok(KRc::new(unsafe { KBox::from_raw(kapi::r1(any.k_ptr() as *mut K)) }))
+    }
+}
+
+impl FromKdb for KRc<Any> {
+    fn from_kdb(any: &Any) -> Result<Self, ConversionError> {
+        Ok(KRc::new(unsafe { KBox::from_raw(kapi::r1(any.k_ptr() as *mut K)) }))
+    }
+}
+
+macro_rules! impl_tuple_try_from {
+    ($len:expr; $($t:ident $i:tt),+) => {
+        /// Destructures a general list into a tuple, converting each item with `FromKdb`. Fails with
+        /// `InvalidLength` if the list doesn't have one item per element, or with `InvalidItem` holding the index
+        /// of the first item that couldn't be converted.
+        impl<$($t: FromKdb),+> TryFrom<&List<Any>> for ($($t,)+) {
+            type Error = ConversionError;
+
+            fn try_from(list: &List<Any>) -> Result<Self, Self::Error> {
+                if list.len() != $len {
+                    return Err(ConversionError::InvalidLength {
+                        expected: $len,
+                        found: list.len(),
+                    });
+                }
+                Ok(($(
+                    $t::from_kdb(&list[$i]).map_err(|e| ConversionError::at_index($i, e))?,
+                )+))
+            }
+        }
+
+        impl<$($t: FromKdb),+> TryFrom<KBox<List<Any>>> for ($($t,)+) {
+            type Error = ConversionError;
+
+            fn try_from(list: KBox<List<Any>>) -> Result<Self, Self::Error> {
+                Self::try_from(&*list)
+            }
+        }
+    };
+}
+
+impl_tuple_try_from!(1; A 0);
+impl_tuple_try_from!(2; A 0, B 1);
+impl_tuple_try_from!(3; A 0, B 1, C 2);
+impl_tuple_try_from!(4; A 0, B 1, C 2, D 3);
+impl_tuple_try_from!(5; A 0, B 1, C 2, D 3, E 4);
+impl_tuple_try_from!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple_try_from!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple_try_from!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_tuple_try_from!(9; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_tuple_try_from!(10; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_tuple_try_from!(11; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, L 10);
+impl_tuple_try_from!(12; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, L 10, M 11);
+
+/// Finds the index of a symbol key in a dictionary.
+fn find_symbol_key(dict: &Dictionary, key: Symbol) -> Result<Option<usize>, ConversionError> {
+    let keys = unsafe { &*((*dict.k_ptr()).union.dict.k as *const Any) };
+    match keys.k_type() {
+        SYMBOL_LIST => Ok(try_cast!(keys; List<Symbol>)?.iter().position(|k| *k == key)),
+        MIXED_LIST => Ok(try_cast!(keys; List<Any>)?.iter().position(|k| {
+            try_cast!(k.as_ref(); Atom<Symbol>)
+                .map(|s| s.value() == key)
+                .unwrap_or(false)
+        })),
+        other => Err(ConversionError::InvalidKCast {
+            from: other,
+            to: SYMBOL_LIST,
+        }),
+    }
+}
+
+/// Calls `f` with the value stored at `index` in the dictionary's value list.
+fn with_value<R>(dict: &Dictionary, index: usize, f: impl FnOnce(&Any) -> R) -> Result<R, ConversionError> {
+    let values: *const K = unsafe { (*dict.k_ptr()).union.dict.v };
+    let t = unsafe { (*values).t };
+    if t == MIXED_LIST || t.is_simple_list() {
+        let len = unsafe { (*values).union.list.n as usize };
+        if index >= len {
+            return Err(ConversionError::InvalidLength {
+                expected: index + 1,
+                found: len,
+            });
+        }
+    }
+    if t == MIXED_LIST {
+        let values = unsafe { &*(values as *const List<Any>) };
+        Ok(f(&values[index]))
+    } else if t.is_simple_list() {
+        let atom = unsafe { simple_item_as_atom(values, index) };
+        Ok(f(&atom))
+    } else {
+        Err(ConversionError::InvalidKCast {
+            from: t,
+            to: MIXED_LIST,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    //! Support functions used by the code generated by `#[derive(IntoKdb, FromKdb)]`.
+    //! These are not part of the public API.
+    use super::*;
+
+    pub use crate::dictionary::Dictionary;
+    pub use crate::symbol::symbol;
+
+    /// Reads and converts the value stored under a symbol key in a dictionary.
+    pub fn get_field<T: FromKdb>(dict: &Dictionary, key: &str) -> Result<T, ConversionError> {
+        match find_symbol_key(dict, symbol(key))? {
+            Some(index) => with_value(dict, index, T::from_kdb)?.map_err(|e| ConversionError::InvalidValue {
+                key: key.to_owned(),
+                source: Box::new(e),
+            }),
+            None => T::from_missing().ok_or_else(|| ConversionError::MissingKey(key.to_owned())),
+        }
+    }
+
+    /// Calls `f` with the mixed list stored under a symbol key in a dictionary.
+    pub fn with_list_field<T>(
+        dict: &Dictionary,
+        key: &str,
+        len: usize,
+        f: impl FnOnce(&List<Any>) -> Result<T, ConversionError>,
+    ) -> Result<T, ConversionError> {
+        let index = find_symbol_key(dict, symbol(key))?.ok_or_else(|| ConversionError::MissingKey(key.to_owned()))?;
+        with_value(dict, index, |value| as_list(value, len).and_then(f))?.map_err(|e| ConversionError::InvalidValue {
+            key: key.to_owned(),
+            source: Box::new(e),
+        })
+    }
+
+    /// Reads the value of a symbol stored under a key in a dictionary.
+    pub fn get_tag(dict: &Dictionary, key: &str) -> Result<String, ConversionError> {
+        get_field::<Symbol>(dict, key).and_then(|s| Ok(s.try_as_str()?.to_owned()))
+    }
+
+    /// Casts a K object to a dictionary.
+    pub fn as_dict(any: &Any) -> Result<&Dictionary, ConversionError> {
+        try_cast!(any; Dictionary)
+    }
+
+    /// Casts a K object to a mixed list with the expected number of items.
+    pub fn as_list(any: &Any, len: usize) -> Result<&List<Any>, ConversionError> {
+        let list = try_cast!(any; List<Any>)?;
+        if list.len() != len {
+            return Err(ConversionError::InvalidLength {
+                expected: len,
+                found: list.len(),
+            });
+        }
+        Ok(list)
+    }
+
+    /// Reads and converts an item in a mixed list.
+    pub fn get_item<T: FromKdb>(list: &List<Any>, index: usize) -> Result<T, ConversionError> {
+        T::from_kdb(&list[index]).map_err(|e| ConversionError::at_index(index, e))
+    }
+
+    /// Returns the symbol value if the K object is a symbol atom.
+    pub fn as_symbol(any: &Any) -> Option<String> {
+        try_cast!(any; Atom<Symbol>)
+            .ok()
+            .and_then(|s| s.value().try_as_str().ok().map(str::to_owned))
+    }
+
+    /// Creates a new, empty dictionary for a struct or enum variant to be written into.
+    pub fn new_dict() -> KBox<Dictionary> {
+        KBox::new_dict()
+    }
+
+    /// Creates a mixed list from a set of converted values.
+    pub fn new_list(items: Vec<KBox<Any>>) -> KBox<Any> {
+        items.into_iter().collect::<KBox<List<Any>>>().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+
+    use super::*;
+    use crate::symbol::symbol;
+    use kdb_derive::{FromKdb, IntoKdb};
+
+    #[derive(Debug, PartialEq, IntoKdb, FromKdb)]
+    struct Config {
+        name: String,
+        port: i32,
+        #[kdb(rename = "ratio")]
+        scale: f64,
+        tags: Vec<Symbol>,
+        timeout: Option<i64>,
+    }
+
+    #[derive(Debug, PartialEq, IntoKdb, FromKdb)]
+    enum Side {
+        Buy,
+        #[kdb(rename = "sell")]
+        Sell,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoKdb, FromKdb)]
+    #[kdb(tag = "kind")]
+    enum Request {
+        Ping,
+        Subscribe { topic: Symbol, depth: i32 },
+        Cancel(i64),
+        Move(i64, f64),
+    }
+
+    #[derive(Debug, PartialEq, IntoKdb, FromKdb)]
+    struct Order(Side, i64);
+
+    fn config() -> Config {
+        Config {
+            name: "feed".to_owned(),
+            port: 5010,
+            scale: 0.5,
+            tags: vec![symbol("a"), symbol("b")],
+            timeout: None,
+        }
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        assert_eq!(i32::from_kdb(&42i32.into_kdb()).unwrap(), 42);
+        assert_eq!(f64::from_kdb(&1.5f64.into_kdb()).unwrap(), 1.5);
+        assert_eq!(String::from_kdb(&"hello".into_kdb()).unwrap(), "hello");
+        assert_eq!(String::from_kdb(&symbol("hello").into_kdb()).unwrap(), "hello");
+        assert_eq!(Option::<i32>::from_kdb(&None::<i32>.into_kdb()).unwrap(), None);
+        assert_eq!(Vec::<i64>::from_kdb(&vec![1i64, 2].into_kdb()).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn unary_primitives_are_not_null() {
+        let k = generic_null();
+        assert!(k.is_null());
+        // the unary primitive -: (neg) has the generic null's type and a nonzero index.
+        unsafe { (*(k.k_ptr() as *mut K)).union.g = 2 };
+        assert!(!k.is_null());
+        assert!(Option::<i32>::from_kdb(&k).is_err());
+    }
+
+    #[test]
+    fn vectors_of_primitives_create_simple_lists() {
+        let k = vec![1i32, 2, 3].into_kdb();
+        assert_eq!(try_cast!(&k; List<i32>).unwrap().as_slice(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn structs_round_trip_through_dictionaries() {
+        let k = config().into_kdb();
+        let dict = try_cast!(&k; Dictionary).unwrap();
+        assert_eq!(dict.len(), 5);
+        assert!(dict.get(symbol("ratio")).is_some());
+        assert_eq!(Config::from_kdb(&k).unwrap(), config());
+    }
+
+    #[test]
+    fn structs_can_be_read_from_symbol_keyed_dictionaries() {
+        let keys = crate::list![Symbol; symbol("a"), symbol("b")];
+        let values = crate::list![i64; 1, 2];
+        let k: KBox<Any> = unsafe { KBox::from_raw(kapi::xD(keys.into_raw() as _, values.into_raw() as _) as _) };
+
+        #[derive(Debug, PartialEq, FromKdb)]
+        struct Pair {
+            a: i64,
+            b: i64,
+        }
+        assert_eq!(Pair::from_kdb(&k).unwrap(), Pair { a: 1, b: 2 });
+    }
+
+    #[test]
+    fn short_value_lists_are_reported() {
+        #[derive(Debug, FromKdb)]
+        struct Pair {
+            _a: i64,
+            _b: i64,
+        }
+        let values: [KBox<Any>; 2] = [crate::list![i64; 1].into(), crate::list![Any; 1i64].into()];
+        for values in values {
+            let keys = crate::list![Symbol; symbol("_a"), symbol("_b")];
+            let k: KBox<Any> = unsafe { KBox::from_raw(kapi::xD(keys.into_raw() as _, values.into_raw() as _) as _) };
+            let err = Pair::from_kdb(&k).unwrap_err();
+            assert!(matches!(err, ConversionError::InvalidLength { expected: 2, found: 1 }));
+        }
+    }
+
+    #[test]
+    fn missing_keys_are_reported() {
+        let mut dict = KBox::new_dict();
+        dict.insert(symbol("name"), "feed".into_kdb());
+        let err = Config::from_kdb(&KBox::<Any>::from(dict)).unwrap_err();
+        assert!(matches!(err, ConversionError::MissingKey(ref key) if key == "port"));
+    }
+
+    #[test]
+    fn mistyped_keys_are_reported() {
+        let mut dict = KBox::new_dict();
+        dict.insert(symbol("name"), "feed".into_kdb());
+        dict.insert(symbol("port"), 5010i64);
+        let err = Config::from_kdb(&KBox::<Any>::from(dict)).unwrap_err();
+        assert!(matches!(err, ConversionError::InvalidValue { ref key, .. } if key == "port"));
+    }
+
+    #[test]
+    fn unit_enums_round_trip_through_symbols() {
+        let k = Side::Sell.into_kdb();
+        assert_eq!(try_cast!(&k; Atom<Symbol>).unwrap().value(), symbol("sell"));
+        assert_eq!(Side::from_kdb(&k).unwrap(), Side::Sell);
+        assert_eq!(Side::from_kdb(&Side::Buy.into_kdb()).unwrap(), Side::Buy);
+        assert!(matches!(
+            Side::from_kdb(&symbol("Hold").into_kdb()),
+            Err(ConversionError::UnknownVariant(ref v)) if v == "Hold"
+        ));
+    }
+
+    #[test]
+    fn data_enums_round_trip_through_tagged_dictionaries() {
+        for request in [
+            Request::Ping,
+            Request::Subscribe {
+                topic: symbol("trade"),
+                depth: 5,
+            },
+            Request::Cancel(12),
+            Request::Move(3, 1.5),
+        ] {
+            let k = request.clone().into_kdb();
+            assert_eq!(Request::from_kdb(&k).unwrap(), request);
+        }
+
+        let k = Request::Cancel(12).into_kdb();
+        let dict = try_cast!(&k; Dictionary).unwrap();
+        assert_eq!(
+            try_cast!(dict.get(symbol("kind")).unwrap(); Atom<Symbol>)
+                .unwrap()
+                .value(),
+            symbol("Cancel")
+        );
+    }
+
+    #[test]
+    fn shared_fields_round_trip() {
+        use crate::{cast, parse_literal, Table};
+
+        #[derive(IntoKdb, FromKdb)]
+        struct Snapshot {
+            name: Symbol,
+            data: KRc<Table>,
+        }
+        let data = KRc::new(cast!(parse_literal("([] px:1.5 2.5)").unwrap(); Table));
+        let snapshot = Snapshot {
+            name: symbol("trade"),
+            data: data.clone(),
+        };
+        let k = snapshot.into_kdb();
+        // the table was shared with `data`, so it was copied into the dictionary.
+        assert_eq!(KRc::ref_count(&data), 1);
+        let read = Snapshot::from_kdb(&k).unwrap();
+        assert_eq!(read.name, symbol("trade"));
+        assert!(*read.data == *data);
+        assert_eq!(KRc::ref_count(&read.data), 2);
+    }
+
+    #[test]
+    fn tuple_structs_round_trip_through_mixed_lists() {
+        let k = Order(Side::Buy, 100).into_kdb();
+        assert_eq!(try_cast!(&k; List<Any>).unwrap().len(), 2);
+        assert_eq!(Order::from_kdb(&k).unwrap(), Order(Side::Buy, 100));
+    }
+
+    #[test]
+    fn general_lists_destructure_into_tuples() {
+        use crate::{cast, parse_literal, Table};
+
+        let msg = cast!(parse_literal("(`upd;`trade;([] px:1.5 2.5))").unwrap(); List<Any>);
+        let (f, t, data): (Symbol, String, KRc<Table>) = TryFrom::try_from(&*msg).unwrap();
+        assert_eq!(f, symbol("upd"));
+        assert_eq!(t, "trade");
+        assert_eq!(data.len(), 2);
+        assert_eq!(KRc::ref_count(&data), 2);
+        let data = KRc::unwrap_or_copy(data).ok().unwrap();
+        assert_ne!(data.k_ptr(), msg[2].k_ptr());
+        assert_eq!(*KBox::<Any>::from(data), *msg[2]);
+
+        match <(Symbol, i64, KRc<Any>)>::try_from(msg.deep_clone().unwrap())
+            .err()
+            .unwrap()
+        {
+            ConversionError::InvalidItem { index: 1, source } => {
+                assert!(matches!(*source, ConversionError::InvalidKCast { .. }))
+            }
+            e => panic!("unexpected error {}", e),
+        }
+        assert!(matches!(
+            <(Symbol, Symbol)>::try_from(&*msg),
+            Err(ConversionError::InvalidLength { expected: 2, found: 3 })
+        ));
+    }
+}
@@ -0,0 +1,213 @@
+//! Copying K objects into fresh allocations.
+use crate::any::Any;
+use crate::atom::Atom;
+use crate::dictionary::{Dict, Dictionary};
+use crate::k::K;
+use crate::k_error::KError;
+use crate::k_type::*;
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::list::List;
+use crate::table::Table;
+use crate::type_traits::KObject;
+use std::ptr;
+
+/// Copies a K object into a newly allocated one with a reference count of one. Mixed lists, dictionaries and
+/// tables are copied recursively, so the copy shares no lists with the original. Simple and enumerated lists are
+/// copied with a single memcpy.
+///
+/// Returns `None` if the object, or anything inside it, can't be copied, such as a function.
+pub(crate) unsafe fn copy_k(k: &K) -> Option<*mut K> {
+    copy(k, false)
+}
+
+/// Copies a K object like `copy_k`, except that objects that can't be copied are shared instead, by incrementing
+/// their reference count. KDB never modifies these objects, so sharing them can't be observed.
+pub(crate) unsafe fn copy_or_share(k: &K) -> *mut K {
+    match copy(k, true) {
+        Some(copy) => copy,
+        None => unreachable!("objects that can't be copied are shared"),
+    }
+}
+
+unsafe fn copy(k: &K, share: bool) -> Option<*mut K> {
+    let copy = match k.t {
+        GUID_ATOM => {
+            // guid atoms are stored as a single item list.
+            let atom = kapi::ktn(GUID_LIST.into(), 1);
+            (*atom).t = GUID_ATOM;
+            ptr::copy_nonoverlapping(k.union.list.g0.as_ptr(), (*atom).union.list.g0.as_mut_ptr(), 16);
+            atom
+        }
+        t if (-76..0).contains(&i32::from(t)) || t == ERROR || t == GENERIC_NULL => {
+            let atom = kapi::ka(t.into());
+            ptr::copy_nonoverlapping(&k.union.j, &mut (*atom).union.j, 1);
+            atom
+        }
+        t if t.is_simple_list() || t.is_enum_list() => {
+            let bytes = k.list_bytes();
+            let list = kapi::ktn(t.into(), k.union.list.n);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), (*list).union.list.g0.as_mut_ptr(), bytes.len());
+            list
+        }
+        MIXED_LIST => {
+            let list = kapi::ktn(MIXED_LIST.into(), k.union.list.n);
+            let items = (*list).union.list.g0.as_mut_ptr() as *mut *mut K;
+            for (i, item) in k.list_items().iter().enumerate() {
+                match copy(item, share) {
+                    Some(item) => *items.add(i) = item,
+                    None => {
+                        // release the items copied so far.
+                        (*list).union.list.n = i as i64;
+                        kapi::r0(list);
+                        return None;
+                    }
+                }
+            }
+            list
+        }
+        DICT => {
+            let keys = copy(&*k.union.dict.k, share)?;
+            match copy(&*k.union.dict.v, share) {
+                Some(values) => kapi::xD(keys, values) as *mut K,
+                None => {
+                    kapi::r0(keys);
+                    return None;
+                }
+            }
+        }
+        TABLE => kapi::xT(copy(&*k.union.k0, share)?) as *mut K,
+        _ if share => return Some(kapi::r1(k as *const K as *mut K)),
+        _ => return None,
+    };
+    (*copy).u = k.u;
+    Some(copy)
+}
+
+unsafe fn deep_clone<T: KObject>(t: &T) -> Option<KBox<T>> {
+    copy_k(&*t.k_ptr()).map(|k| KBox::from_raw(k))
+}
+
+macro_rules! impl_deep_clone {
+    ($($t:ty),*) => {
+        $(
+            impl $t {
+                /// Copies the value into a fresh allocation. Any lists, dictionaries and tables it contains are
+                /// copied as well, so changing the copy never affects the original. Returns `None` if the value
+                /// contains a function, or anything else that can't be copied.
+                pub fn deep_clone(&self) -> Option<KBox<Self>> {
+                    unsafe { deep_clone(self) }
+                }
+            }
+        )*
+    };
+}
+
+impl_deep_clone!(Any, Dictionary, Table, KError);
+
+impl<T> List<T> {
+    /// Copies the list into a fresh allocation. Simple lists are copied with a single memcpy, and the items of
+    /// mixed lists are copied recursively, so changing the copy never affects the original. Returns `None` if the
+    /// list contains a function, or anything else that can't be copied.
+    pub fn deep_clone(&self) -> Option<KBox<Self>> {
+        unsafe { deep_clone(self) }
+    }
+}
+
+impl<Key, Value> Dict<Key, Value> {
+    /// Copies the dictionary into a fresh allocation, including its key and value lists. Returns `None` if it
+    /// contains a function, or anything else that can't be copied.
+    pub fn deep_clone(&self) -> Option<KBox<Self>> {
+        unsafe { deep_clone(self) }
+    }
+}
+
+impl<T> Atom<T> {
+    /// Copies the atom into a fresh allocation.
+    pub fn deep_clone(&self) -> KBox<Self> {
+        unsafe { KBox::from_raw(copy_or_share(&*self.k_ptr())) }
+    }
+}
+
+/// Cloning a `KBox` makes a deep copy of the value it points to, in the same way as a Rust `Box`.
+///
+/// Functions can't be copied, so any functions in the value (or the value itself, if it is a function) are shared
+/// with the original by incrementing their reference count instead. KDB never modifies a function once it has been
+/// created, and nothing in this crate can modify one either, so the sharing can't be observed. Use `deep_clone`
+/// to get a copy that is guaranteed to share nothing.
+impl<T: KObject> Clone for KBox<T> {
+    fn clone(&self) -> Self {
+        unsafe { KBox::from_raw(copy_or_share(&*self.k_ptr())) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::k_type::KTypeCode;
+    use crate::type_traits::KObject;
+    use crate::{cast, list, parse_literal, Any, Dictionary, KBox, List, Table};
+
+    #[test]
+    fn deep_clone_copies_simple_lists() {
+        let a = list![i64; 1, 2, 3];
+        let mut b = a.deep_clone().unwrap();
+        b[0] = 10;
+        b.push(4);
+        assert_eq!(a.as_slice(), &[1, 2, 3]);
+        assert_eq!(b.as_slice(), &[10, 2, 3, 4]);
+    }
+
+    #[test]
+    fn deep_clone_copies_nested_values() {
+        let a = parse_literal("(1 2;`a`b!(3 4;\"xy\");([] c:1 2))").unwrap();
+        let b = a.clone();
+        assert_eq!(a, b);
+
+        let (a, b) = (cast!(&*a; List<Any>), cast!(&*b; List<Any>));
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert_ne!(x.k_ptr(), y.k_ptr());
+        }
+        let (x, y) = (cast!(&b[1]; Dictionary), cast!(&a[1]; Dictionary));
+        assert_ne!(x.values()[0].k_ptr(), y.values()[0].k_ptr());
+        let (x, y) = (cast!(&b[2]; Table), cast!(&a[2]; Table));
+        assert_ne!(x.columns().k_ptr(), y.columns().k_ptr());
+    }
+
+    #[test]
+    fn deep_clone_copies_atoms_and_attributes() {
+        let mut a = list![i64; 3, 1, 2];
+        a.sort();
+        let b = a.deep_clone().unwrap();
+        assert_eq!(a.as_slice(), b.as_slice());
+        assert!(unsafe { (*b.k_ptr()).attr().sorted() });
+        let atom = KBox::new_atom(1.5);
+        assert_eq!(atom.deep_clone().value(), 1.5);
+    }
+
+    #[test]
+    fn deep_clone_copies_enumerations() {
+        let mut a = list![i64; 0, 1, 0];
+        // turn the long list into an enumeration over the domain with type 20.
+        unsafe { *(&mut (*a.k_ptr_mut()).t as *mut KTypeCode as *mut i8) = 20 };
+        let b = a.deep_clone().unwrap();
+        assert_ne!(a.k_ptr(), b.k_ptr());
+        unsafe {
+            assert_eq!(i32::from((*b.k_ptr()).t), 20);
+            assert_eq!((*b.k_ptr()).list_bytes(), (*a.k_ptr()).list_bytes());
+        }
+    }
+
+    #[test]
+    fn functions_are_shared_rather_than_copied() {
+        let mut a: KBox<List<Any>> = list![Any; 1i64, 2i64];
+        let f = a[1].k_ptr_mut();
+        // pretend the second item is a lambda.
+        unsafe { *(&mut (*f).t as *mut KTypeCode as *mut i8) = 100 };
+        assert!(a.deep_clone().is_none());
+        let b = a.clone();
+        assert_ne!(a.k_ptr(), b.k_ptr());
+        assert_eq!(b[1].k_ptr(), f as *const _);
+        assert_ne!(b[0].k_ptr(), a[0].k_ptr());
+        unsafe { *(&mut (*f).t as *mut KTypeCode as *mut i8) = -7 };
+    }
+}
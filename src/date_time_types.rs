@@ -1,12 +1,233 @@
+use crate::error::{ParseError, ParseErrorKind};
 use crate::kapi;
+use crate::parser::Cursor;
 use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
 pub(crate) const K_NANO_OFFSET: i64 = 946_684_800_000_000_000;
 pub(crate) const K_SEC_OFFSET: i64 = K_NANO_OFFSET / 1_000_000_000;
 pub(crate) const K_DAY_OFFSET: i32 = (K_SEC_OFFSET / 86_400) as i32;
 
+/// Returns the number of nanoseconds from the unix epoch to `st`, which is negative for times before 1970.
+fn nanos_since_unix_epoch(st: SystemTime) -> i128 {
+    match st.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+/// Clamps a value to the range of `i64` that isn't null (`0N`) or infinite (`0W` and `-0W`).
+fn saturate_i64(value: i128) -> i64 {
+    value.clamp(-(i64::MAX as i128) + 1, i64::MAX as i128 - 1) as i64
+}
+
+/// Clamps a value to the range of `i32` that isn't null (`0N`) or infinite (`0W` and `-0W`).
+fn saturate_i32(value: i128) -> i32 {
+    value.clamp(-(i32::MAX as i128) + 1, i32::MAX as i128 - 1) as i32
+}
+
+fn system_time_from_unix_nanos(nanos: i128) -> SystemTime {
+    let d = Duration::new(
+        (nanos.unsigned_abs() / 1_000_000_000) as u64,
+        (nanos.unsigned_abs() % 1_000_000_000) as u32,
+    );
+    if nanos >= 0 {
+        SystemTime::UNIX_EPOCH + d
+    } else {
+        SystemTime::UNIX_EPOCH - d
+    }
+}
+
+/// Returns the number of days from 1 Jan 1970 to the specified date in the proleptic Gregorian calendar.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the (year, month, day) of the date that is `days` days from 1 Jan 1970.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+const NANOS_PER_DAY: i64 = 86_400 * NANOS_PER_SEC;
+
+/// The null and infinite values of the temporal types, which q writes as `0N`, `0W` and `-0W` followed by the type character.
+#[derive(Clone, Copy)]
+enum Special {
+    Null,
+    Infinity,
+    NegativeInfinity,
+}
+
+impl Special {
+    fn from_i32(v: i32) -> Option<Special> {
+        match v {
+            i32::MIN => Some(Special::Null),
+            i32::MAX => Some(Special::Infinity),
+            v if v == -i32::MAX => Some(Special::NegativeInfinity),
+            _ => None,
+        }
+    }
+
+    fn from_i64(v: i64) -> Option<Special> {
+        match v {
+            i64::MIN => Some(Special::Null),
+            i64::MAX => Some(Special::Infinity),
+            v if v == -i64::MAX => Some(Special::NegativeInfinity),
+            _ => None,
+        }
+    }
+
+    fn from_sign(v: f64) -> Special {
+        if v < 0.0 {
+            Special::NegativeInfinity
+        } else {
+            Special::Infinity
+        }
+    }
+
+    fn parse(s: &str, type_char: char) -> Option<Special> {
+        match s.strip_suffix(type_char)? {
+            "0N" => Some(Special::Null),
+            "0W" => Some(Special::Infinity),
+            "-0W" => Some(Special::NegativeInfinity),
+            _ => None,
+        }
+    }
+
+    fn as_i32(self) -> i32 {
+        match self {
+            Special::Null => i32::MIN,
+            Special::Infinity => i32::MAX,
+            Special::NegativeInfinity => -i32::MAX,
+        }
+    }
+
+    fn as_i64(self) -> i64 {
+        match self {
+            Special::Null => i64::MIN,
+            Special::Infinity => i64::MAX,
+            Special::NegativeInfinity => -i64::MAX,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Special::Null => f64::NAN,
+            Special::Infinity => f64::INFINITY,
+            Special::NegativeInfinity => f64::NEG_INFINITY,
+        }
+    }
+
+    fn fmt(self, f: &mut fmt::Formatter, type_char: char) -> fmt::Result {
+        match self {
+            Special::Null => write!(f, "0N{}", type_char),
+            Special::Infinity => write!(f, "0W{}", type_char),
+            Special::NegativeInfinity => write!(f, "-0W{}", type_char),
+        }
+    }
+}
+
+fn write_sign(f: &mut fmt::Formatter, negative: bool) -> fmt::Result {
+    if negative {
+        f.write_str("-")?;
+    }
+    Ok(())
+}
+
+fn with_sign(negative: bool, value: i64) -> i64 {
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Writes a date given as the number of days since 1 Jan 2000, as `yyyy.mm.dd`.
+fn write_date(f: &mut fmt::Formatter, days: i64) -> fmt::Result {
+    let (year, month, day) = civil_from_days(days + i64::from(K_DAY_OFFSET));
+    write!(f, "{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Writes a non-negative number of nanoseconds as `hh:mm`, `hh:mm:ss` or `hh:mm:ss.fff...`, with
+/// `fraction_digits` digits after the decimal point.
+fn write_clock(f: &mut fmt::Formatter, nanos: i64, seconds: bool, fraction_digits: u32) -> fmt::Result {
+    let secs = nanos / NANOS_PER_SEC;
+    write!(f, "{:02}:{:02}", secs / 3600, secs / 60 % 60)?;
+    if seconds {
+        write!(f, ":{:02}", secs % 60)?;
+    }
+    if fraction_digits > 0 {
+        let fraction = nanos % NANOS_PER_SEC / 10i64.pow(9 - fraction_digits);
+        write!(f, ".{:0width$}", fraction, width = fraction_digits as usize)?;
+    }
+    Ok(())
+}
+
+/// Parses a date written as `yyyy.mm.dd`, returning the number of days since 1 Jan 2000.
+fn parse_date(c: &mut Cursor) -> Result<i64, ParseError> {
+    let year = c.number(4, 0..=9999, "year")? as i64;
+    c.expect(b'.', "'.'")?;
+    let month = c.number(2, 1..=12, "month")? as u32;
+    c.expect(b'.', "'.'")?;
+    let days_in_month = if month == 12 {
+        31
+    } else {
+        days_from_civil(year, month + 1, 1) - days_from_civil(year, month, 1)
+    };
+    let day = c.number(2, 1..=days_in_month as u64, "day")? as u32;
+    Ok(days_from_civil(year, month, day) - i64::from(K_DAY_OFFSET))
+}
+
+/// Parses a time written as `hh:mm`, `hh:mm:ss` or `hh:mm:ss.fff...` with at most `fraction_digits` digits
+/// after the decimal point, returning the number of nanoseconds.
+fn parse_clock(c: &mut Cursor, max_hour: u64, fraction_digits: usize) -> Result<i64, ParseError> {
+    let hours = c.number(2, 0..=max_hour, "hour")? as i64;
+    c.expect(b':', "':'")?;
+    let minutes = c.number(2, 0..=59, "minute")? as i64;
+    let mut nanos = (hours * 3600 + minutes * 60) * NANOS_PER_SEC;
+    if c.eat(b':') {
+        nanos += c.number(2, 0..=59, "second")? as i64 * NANOS_PER_SEC;
+        if fraction_digits > 0 && c.eat(b'.') {
+            let (fraction, digits) = c.digits(1, fraction_digits, "digit")?;
+            nanos += fraction as i64 * 10i64.pow(9 - digits as u32);
+        }
+    }
+    Ok(nanos)
+}
+
+/// Checks that a number of nanoseconds fits in a timestamp or timespan without being a null or infinity.
+/// Overflows are reported at `position`, the start of the field that caused them.
+fn checked_nanos(nanos: i128, position: usize) -> Result<i64, ParseError> {
+    if fits_nanos(nanos) {
+        Ok(nanos as i64)
+    } else {
+        Err(Cursor::error_at(position, ParseErrorKind::OutOfRange))
+    }
+}
+
+#[inline]
+fn fits_nanos(nanos: i128) -> bool {
+    nanos.abs() < i128::from(i64::MAX)
+}
+
 /// Represents the number of seconds since midnight (00:00)
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -33,7 +254,26 @@ impl From<Second> for i32 {
 
 impl fmt::Display for Second {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} seconds", self.0)
+        if let Some(special) = Special::from_i32(self.0) {
+            return special.fmt(f, 'v');
+        }
+        write_sign(f, self.0 < 0)?;
+        write_clock(f, i64::from(self.0.unsigned_abs()) * NANOS_PER_SEC, true, 0)
+    }
+}
+
+impl FromStr for Second {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'v') {
+            return Ok(Second(special.as_i32()));
+        }
+        let mut c = Cursor::new(s);
+        let negative = c.eat(b'-');
+        let nanos = parse_clock(&mut c, 99, 0)?;
+        c.finish()?;
+        Ok(Second(with_sign(negative, nanos / NANOS_PER_SEC) as i32))
     }
 }
 
@@ -69,7 +309,28 @@ impl From<Minute> for i32 {
 
 impl fmt::Display for Minute {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} minutes", self.0)
+        if let Some(special) = Special::from_i32(self.0) {
+            return special.fmt(f, 'u');
+        }
+        write_sign(f, self.0 < 0)?;
+        write_clock(f, i64::from(self.0.unsigned_abs()) * 60 * NANOS_PER_SEC, false, 0)
+    }
+}
+
+impl FromStr for Minute {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'u') {
+            return Ok(Minute(special.as_i32()));
+        }
+        let mut c = Cursor::new(s);
+        let negative = c.eat(b'-');
+        let hours = c.number(2, 0..=99, "hour")?;
+        c.expect(b':', "':'")?;
+        let minutes = c.number(2, 0..=59, "minute")?;
+        c.finish()?;
+        Ok(Minute(with_sign(negative, (hours * 60 + minutes) as i64) as i32))
     }
 }
 
@@ -108,18 +369,41 @@ impl From<Date> for i32 {
     }
 }
 
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match Special::from_i32(self.0) {
+            Some(special) => special.fmt(f, 'd'),
+            None => write_date(f, self.0.into()),
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'd') {
+            return Ok(Date(special.as_i32()));
+        }
+        let mut c = Cursor::new(s);
+        let days = parse_date(&mut c)?;
+        c.finish()?;
+        Ok(Date(days as i32))
+    }
+}
+
+/// Times outside the range of a date saturate to the earliest or latest date that isn't infinite.
 impl From<SystemTime> for Date {
     fn from(st: SystemTime) -> Date {
-        let dur = st.duration_since(SystemTime::UNIX_EPOCH).unwrap();
-        let dur_secs = (dur.as_secs() / 86400) as i64;
-        Date(dur_secs as i32 - K_DAY_OFFSET)
+        let days = nanos_since_unix_epoch(st).div_euclid(86_400_000_000_000);
+        Date(saturate_i32(days - K_DAY_OFFSET as i128))
     }
 }
 
 impl From<Date> for SystemTime {
     fn from(date: Date) -> SystemTime {
-        let secs = date.0 as i64 * 86400 + K_SEC_OFFSET;
-        SystemTime::UNIX_EPOCH + Duration::from_secs(secs as u64)
+        let secs = date.0 as i128 * 86400 + K_SEC_OFFSET as i128;
+        system_time_from_unix_nanos(secs * 1_000_000_000)
     }
 }
 
@@ -149,7 +433,28 @@ impl From<Month> for i32 {
 
 impl fmt::Display for Month {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} months", self.0)
+        if let Some(special) = Special::from_i32(self.0) {
+            return special.fmt(f, 'm');
+        }
+        let year = 2000 + i64::from(self.0).div_euclid(12);
+        write!(f, "{:04}.{:02}m", year, self.0.rem_euclid(12) + 1)
+    }
+}
+
+impl FromStr for Month {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'm') {
+            return Ok(Month(special.as_i32()));
+        }
+        let mut c = Cursor::new(s);
+        let year = c.number(4, 0..=9999, "year")?;
+        c.expect(b'.', "'.'")?;
+        let month = c.number(2, 1..=12, "month")?;
+        c.expect(b'm', "'m'")?;
+        c.finish()?;
+        Ok(Month(((year as i32) - 2000) * 12 + month as i32 - 1))
     }
 }
 
@@ -183,6 +488,31 @@ impl From<Time> for i32 {
     }
 }
 
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(special) = Special::from_i32(self.0) {
+            return special.fmt(f, 't');
+        }
+        write_sign(f, self.0 < 0)?;
+        write_clock(f, i64::from(self.0.unsigned_abs()) * 1_000_000, true, 3)
+    }
+}
+
+impl FromStr for Time {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 't') {
+            return Ok(Time(special.as_i32()));
+        }
+        let mut c = Cursor::new(s);
+        let negative = c.eat(b'-');
+        let nanos = parse_clock(&mut c, 99, 3)?;
+        c.finish()?;
+        Ok(Time(with_sign(negative, nanos / 1_000_000) as i32))
+    }
+}
+
 /// Represents a date and time in KDB. Conversions between the
 /// Unix Epoch and the KDB Epoch are done automatically.
 ///
@@ -211,6 +541,38 @@ impl From<DateTime> for f64 {
     }
 }
 
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let millis = (self.0 * 86_400_000.0).round();
+        if self.0.is_nan() {
+            Special::Null.fmt(f, 'z')
+        } else if millis.abs() >= i64::MAX as f64 {
+            Special::from_sign(self.0).fmt(f, 'z')
+        } else {
+            let millis = millis as i64;
+            write_date(f, millis.div_euclid(86_400_000))?;
+            f.write_str("T")?;
+            write_clock(f, millis.rem_euclid(86_400_000) * 1_000_000, true, 3)
+        }
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'z') {
+            return Ok(DateTime(special.as_f64()));
+        }
+        let mut c = Cursor::new(s);
+        let days = parse_date(&mut c)?;
+        c.expect(b'T', "'T'")?;
+        let nanos = if c.is_at_end() { 0 } else { parse_clock(&mut c, 23, 3)? };
+        c.finish()?;
+        Ok(DateTime(days as f64 + (nanos / 1_000_000) as f64 / 86_400_000.0))
+    }
+}
+
 /// Represents a timestamp in KDB. Conversions between the
 /// Unix Epoch and the KDB Epoch are done automatically.
 #[repr(transparent)]
@@ -241,14 +603,15 @@ impl Timestamp {
 
 impl From<Timestamp> for SystemTime {
     fn from(date: Timestamp) -> SystemTime {
-        SystemTime::UNIX_EPOCH + Duration::from_nanos(date.as_nanos_unix())
+        system_time_from_unix_nanos(date.0 as i128 + K_NANO_OFFSET as i128)
     }
 }
 
+/// Times outside the range of a timestamp, which ends in 2292, saturate to the earliest or latest timestamp that
+/// isn't infinite.
 impl From<SystemTime> for Timestamp {
     fn from(st: SystemTime) -> Timestamp {
-        let dur = st.duration_since(SystemTime::UNIX_EPOCH).unwrap();
-        Timestamp::from_nanos_unix(dur.as_nanos() as u64)
+        Timestamp(saturate_i64(nanos_since_unix_epoch(st) - K_NANO_OFFSET as i128))
     }
 }
 
@@ -264,6 +627,37 @@ impl From<Timestamp> for i64 {
     }
 }
 
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(special) = Special::from_i64(self.0) {
+            return special.fmt(f, 'p');
+        }
+        write_date(f, self.0.div_euclid(NANOS_PER_DAY))?;
+        f.write_str("D")?;
+        write_clock(f, self.0.rem_euclid(NANOS_PER_DAY), true, 9)
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'p') {
+            return Ok(Timestamp(special.as_i64()));
+        }
+        let mut c = Cursor::new(s);
+        let days = parse_date(&mut c)?;
+        c.expect(b'D', "'D'")?;
+        let clock = c.pos();
+        let nanos = if c.is_at_end() { 0 } else { parse_clock(&mut c, 23, 9)? };
+        c.finish()?;
+        let days = i128::from(days) * i128::from(NANOS_PER_DAY);
+        // blame the date if it overflows by itself, otherwise the time of day that pushed it over.
+        let position = if fits_nanos(days) { clock } else { 0 };
+        checked_nanos(days + i128::from(nanos), position).map(Timestamp)
+    }
+}
+
 /// Represents an elapsed span of time
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -307,6 +701,50 @@ impl From<Timespan> for i64 {
     }
 }
 
+impl fmt::Display for Timespan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(special) = Special::from_i64(self.0) {
+            return special.fmt(f, 'n');
+        }
+        write_sign(f, self.0 < 0)?;
+        let nanos = self.0.unsigned_abs() as i64;
+        write!(f, "{}D", nanos / NANOS_PER_DAY)?;
+        write_clock(f, nanos % NANOS_PER_DAY, true, 9)
+    }
+}
+
+impl FromStr for Timespan {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if let Some(special) = Special::parse(s, 'n') {
+            return Ok(Timespan(special.as_i64()));
+        }
+        let mut c = Cursor::new(s);
+        let negative = c.eat(b'-');
+        // The day count is optional: `00:01:00.000000000` is the same as `0D00:01:00.000000000`.
+        let start = c.pos();
+        let days = match c.digits(1, 6, "digit") {
+            Ok((days, _)) if c.eat(b'D') => Some(days),
+            _ => {
+                c.reset(start);
+                None
+            }
+        };
+        let clock = c.pos();
+        let nanos = if c.is_at_end() && days.is_some() {
+            0
+        } else {
+            parse_clock(&mut c, 23, 9)?
+        };
+        c.finish()?;
+        let days = i128::from(days.unwrap_or(0)) * i128::from(NANOS_PER_DAY);
+        let position = if fits_nanos(days) { clock } else { start };
+        let nanos = days + i128::from(nanos);
+        checked_nanos(if negative { -nanos } else { nanos }, position).map(Timespan)
+    }
+}
+
 impl From<Timespan> for Duration {
     fn from(span: Timespan) -> Duration {
         Duration::from_nanos(span.0 as u64)
@@ -318,10 +756,115 @@ impl TryFrom<Duration> for Timespan {
 
     fn try_from(d: Duration) -> Result<Timespan, Self::Error> {
         let d = d.as_nanos();
-        if d > std::i64::MAX as u128 {
+        if d > i64::MAX as u128 {
             Err(crate::error::ConversionError::DurationTooLong)
         } else {
             Ok(Timespan(d as i64))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_times_before_1970_convert_to_dates_and_timestamps() {
+        let st = SystemTime::UNIX_EPOCH - Duration::from_secs(86_400 + 1);
+        let date = Date::from(st);
+        assert_eq!(date.as_raw(), -K_DAY_OFFSET - 2);
+        assert_eq!(
+            SystemTime::from(date),
+            SystemTime::UNIX_EPOCH - Duration::from_secs(2 * 86_400)
+        );
+
+        let ts = Timestamp::from(st);
+        assert_eq!(ts.as_raw(), -K_NANO_OFFSET - 86_401_000_000_000);
+        assert_eq!(SystemTime::from(ts), st);
+    }
+
+    #[test]
+    fn far_off_system_times_saturate() {
+        let year = Duration::from_secs(365 * 86_400);
+        let future = SystemTime::UNIX_EPOCH + year * 400;
+        assert_eq!(Timestamp::from(future).as_raw(), i64::MAX - 1);
+        let past = SystemTime::UNIX_EPOCH - year * 400;
+        assert_eq!(Timestamp::from(past).as_raw(), -i64::MAX + 1);
+
+        let far_future = SystemTime::UNIX_EPOCH + year * 10_000_000;
+        assert_eq!(Date::from(far_future).as_raw(), i32::MAX - 1);
+        let far_past = SystemTime::UNIX_EPOCH - year * 10_000_000;
+        assert_eq!(Date::from(far_past).as_raw(), -i32::MAX + 1);
+    }
+
+    #[test]
+    fn temporal_types_display_as_q_literals() {
+        let ts: Timestamp = "2020.02.12D10:15:00.000000000".parse().unwrap();
+        assert_eq!(ts.to_string(), "2020.02.12D10:15:00.000000000");
+        assert_eq!(Timestamp::from_raw(-1).to_string(), "1999.12.31D23:59:59.999999999");
+        assert_eq!(Timestamp::from_raw(i64::MIN).to_string(), "0Np");
+        assert_eq!(Month::from(241).to_string(), "2020.02m");
+        assert_eq!(Month::from(-1).to_string(), "1999.12m");
+        assert_eq!(Date::new(2020, 2, 12).to_string(), "2020.02.12");
+        assert_eq!(Time::from(36_930_123).to_string(), "10:15:30.123");
+        assert_eq!(Time::from(-1_000).to_string(), "-00:00:01.000");
+        assert_eq!(Minute::from(615).to_string(), "10:15");
+        assert_eq!(Second::from(36_930).to_string(), "10:15:30");
+        assert_eq!(Second::from(i32::MAX).to_string(), "0Wv");
+        assert_eq!(Timespan::from_secs(60).to_string(), "0D00:01:00.000000000");
+        assert_eq!(Timespan::from_millis(-90_000_000).to_string(), "-1D01:00:00.000000000");
+        assert_eq!(DateTime::from(0.5).to_string(), "2000.01.01T12:00:00.000");
+        assert_eq!(DateTime::from(f64::NAN).to_string(), "0Nz");
+    }
+
+    #[test]
+    fn temporal_types_parse_q_literals() {
+        assert_eq!("2020.02m".parse::<Month>().unwrap(), Month::from(241));
+        assert_eq!("2000.01.01".parse::<Date>().unwrap(), Date::from(0));
+        assert_eq!(
+            "2000.01.01D00:00:01.5".parse::<Timestamp>().unwrap(),
+            Timestamp::from_raw(1_500_000_000)
+        );
+        assert_eq!("-0D00:00:01".parse::<Timespan>().unwrap(), Timespan::from_secs(-1));
+        assert_eq!(
+            "00:01:00.000000001".parse::<Timespan>().unwrap(),
+            Timespan::from_nanos(60_000_000_001)
+        );
+        assert_eq!("10:15:30.123".parse::<Time>().unwrap(), Time::from(36_930_123));
+        assert_eq!("10:15".parse::<Minute>().unwrap(), Minute::from(615));
+        assert_eq!("10:15:30".parse::<Second>().unwrap(), Second::from(36_930));
+        assert_eq!(
+            "1999.12.31T12:00:00.000".parse::<DateTime>().unwrap(),
+            DateTime::from(-0.5)
+        );
+        assert_eq!("0Nd".parse::<Date>().unwrap(), Date::from(i32::MIN));
+        assert_eq!("-0Wn".parse::<Timespan>().unwrap(), Timespan::from_nanos(-i64::MAX));
+    }
+
+    #[test]
+    fn parse_errors_report_their_position() {
+        let err = "2020.13m".parse::<Month>().unwrap_err();
+        assert_eq!((err.position, err.kind), (5, ParseErrorKind::OutOfRange));
+        let err = "2021.02.29".parse::<Date>().unwrap_err();
+        assert_eq!((err.position, err.kind), (8, ParseErrorKind::OutOfRange));
+        let err = "2020.02.12X10:15".parse::<Timestamp>().unwrap_err();
+        assert_eq!((err.position, err.kind), (10, ParseErrorKind::Expected("'D'")));
+        let err = "10:15:30.1234".parse::<Time>().unwrap_err();
+        assert_eq!((err.position, err.kind), (12, ParseErrorKind::TrailingCharacters));
+        let err = "10:1".parse::<Minute>().unwrap_err();
+        assert_eq!((err.position, err.kind), (3, ParseErrorKind::Expected("minute")));
+    }
+
+    #[test]
+    fn overflows_are_reported_at_the_field_that_caused_them() {
+        let err = "2292.04.11D00:00".parse::<Timestamp>().unwrap_err();
+        assert_eq!((err.position, err.kind), (0, ParseErrorKind::OutOfRange));
+        let err = "2292.04.10D23:59".parse::<Timestamp>().unwrap_err();
+        assert_eq!((err.position, err.kind), (11, ParseErrorKind::OutOfRange));
+        let err = "-999999D".parse::<Timespan>().unwrap_err();
+        assert_eq!((err.position, err.kind), (1, ParseErrorKind::OutOfRange));
+        let err = "-106751D23:59".parse::<Timespan>().unwrap_err();
+        assert_eq!((err.position, err.kind), (8, ParseErrorKind::OutOfRange));
+        assert!("2292.04.10D23:47".parse::<Timestamp>().is_ok());
+    }
+}
@@ -0,0 +1,547 @@
+//! A serde `Deserializer` that reads Rust values from K objects.
+//!
+//! This is the reverse of the mapping used by `to_any`. Atoms holding the null value for their type,
+//! and the generic null `(::)`, are deserialized as `None` when an `Option` is expected. Tables are
+//! read as sequences of rows, with each row read as a map (or struct) of column names to values.
+//! Temporal types are read as their underlying numeric values.
+use crate::any::Any;
+use crate::atom::Atom;
+use crate::error::ConversionError;
+use crate::k_type::*;
+use crate::kbox::KBox;
+use crate::list::{simple_item_as_atom, List};
+use crate::symbol::Symbol;
+use crate::table::Table;
+use crate::type_traits::KObject;
+use crate::{k_error::KError, try_cast};
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::slice;
+
+impl de::Error for ConversionError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ConversionError::Custom(msg.to_string())
+    }
+}
+
+/// Deserializes a Rust value from a K object.
+///
+/// # Example
+/// ```
+/// use kdb::{from_any, list};
+///
+/// let k = list![i64; 1, 2, 3];
+/// let v: Vec<i64> = from_any(k.as_ref()).unwrap();
+/// assert_eq!(v, vec![1, 2, 3]);
+/// ```
+pub fn from_any<'de, T: Deserialize<'de>>(any: &'de Any) -> Result<T, ConversionError> {
+    T::deserialize(Deserializer::new(any))
+}
+
+enum Value<'de> {
+    Borrowed(&'de Any),
+    /// An atom copied out of a simple list.
+    Owned(KBox<Any>),
+    /// A row of a table.
+    Row(&'de Table, usize),
+}
+
+/// A serde `Deserializer` that reads from K objects.
+pub struct Deserializer<'de> {
+    value: Value<'de>,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Creates a deserializer that reads from the specified K object.
+    pub fn new(any: &'de Any) -> Self {
+        Deserializer {
+            value: Value::Borrowed(any),
+        }
+    }
+
+    /// Creates a deserializer for the item at `index` in a list or table.
+    fn item(list: &'de Any, index: usize) -> Self {
+        let value = match list.k_type() {
+            MIXED_LIST => Value::Borrowed(&try_cast!(list; List<Any>).unwrap()[index]),
+            TABLE => Value::Row(try_cast!(list; Table).unwrap(), index),
+            _ => Value::Owned(unsafe { simple_item_as_atom(list.k_ptr(), index) }),
+        };
+        Deserializer { value }
+    }
+
+    fn any(&self) -> Option<&Any> {
+        match &self.value {
+            Value::Borrowed(any) => Some(any),
+            Value::Owned(any) => Some(any),
+            Value::Row(..) => None,
+        }
+    }
+}
+
+fn len(any: &Any) -> usize {
+    if any.k_type() == TABLE {
+        try_cast!(any; Table).unwrap().len()
+    } else {
+        any.list_len().unwrap_or(0)
+    }
+}
+
+fn visit_atom<'de, V: Visitor<'de>>(any: &Any, visitor: V) -> Result<V::Value, ConversionError> {
+    unsafe {
+        let u = &(*any.k_ptr()).union;
+        match any.k_type() {
+            GENERIC_NULL if any.is_generic_null() => visitor.visit_unit(),
+            BOOLEAN_ATOM => visitor.visit_bool(u.bl),
+            BYTE_ATOM => visitor.visit_u8(u.g),
+            SHORT_ATOM => visitor.visit_i16(u.h),
+            INT_ATOM | MONTH_ATOM | DATE_ATOM | MINUTE_ATOM | SECOND_ATOM | TIME_ATOM => visitor.visit_i32(u.i),
+            LONG_ATOM | TIMESTAMP_ATOM | TIMESPAN_ATOM => visitor.visit_i64(u.j),
+            REAL_ATOM => visitor.visit_f32(u.e),
+            FLOAT_ATOM | DATE_TIME_ATOM => visitor.visit_f64(u.f),
+            CHAR_ATOM => visitor.visit_char(u.g as char),
+            SYMBOL_ATOM => visitor.visit_borrowed_str(u.sym.try_as_str()?),
+            GUID_ATOM => visitor.visit_bytes(slice::from_raw_parts(&u.list.g0 as *const u8, 16)),
+            ERROR => Err(ConversionError::Custom(
+                (*(any as *const Any as *const KError)).to_string(),
+            )),
+            t => Err(ConversionError::Custom(format!("Can't deserialize a {}", t))),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Row(table, row) => visitor.visit_map(RowAccess { table, row, column: 0 }),
+            Value::Owned(any) => visit_atom(&any, visitor),
+            Value::Borrowed(any) => match any.k_type() {
+                CHAR_LIST => visitor.visit_borrowed_str(try_cast!(any; List<i8>)?.try_as_str()?),
+                BYTE_LIST => visitor.visit_borrowed_bytes(try_cast!(any; List<u8>)?.as_slice()),
+                DICT => visitor.visit_map(DictAccess::new(any)?),
+                t if t == MIXED_LIST || t == TABLE || t.is_simple_list() => {
+                    visitor.visit_seq(ListAccess { list: any, index: 0 })
+                }
+                _ => visit_atom(any, visitor),
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        if self.any().map(Any::is_null).unwrap_or(false) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        if self.any().map(Any::is_generic_null).unwrap_or(false) {
+            visitor.visit_unit()
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.any() {
+            Some(any) if any.k_type() == CHAR_ATOM => {
+                let c = unsafe { (*any.k_ptr()).union.g } as char;
+                visitor.visit_str(c.encode_utf8(&mut [0; 4]))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Borrowed(any) if any.k_type() == CHAR_LIST => {
+                visitor.visit_borrowed_bytes(try_cast!(any; List<i8>)?.try_as_str()?.as_bytes())
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            // Treat strings and byte lists as lists when a sequence is expected.
+            Value::Borrowed(any) if any.k_type() == CHAR_LIST || any.k_type() == BYTE_LIST => {
+                visitor.visit_seq(ListAccess { list: any, index: 0 })
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Borrowed(any) if any.k_type() == DICT => {
+                let dict = DictAccess::new(any)?;
+                if dict.len != 1 || dict.keys.k_type() != SYMBOL_LIST {
+                    return Err(ConversionError::Custom(
+                        "Expected a dictionary with a single symbol key for an enum".to_owned(),
+                    ));
+                }
+                let variant = try_cast!(dict.keys; List<Symbol>)?[0].try_as_str()?;
+                visitor.visit_enum(VariantAccess {
+                    variant,
+                    value: Deserializer::item(dict.values, 0),
+                })
+            }
+            _ => match self.any() {
+                Some(any) if any.k_type() == SYMBOL_ATOM => {
+                    let variant = try_cast!(any; Atom<Symbol>)?.value().try_as_str()?;
+                    visitor.visit_enum(variant.into_deserializer())
+                }
+                _ => Err(ConversionError::Custom(
+                    "Expected a symbol or a dictionary for an enum".to_owned(),
+                )),
+            },
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char map struct
+    }
+}
+
+/// Reads the items of a list, or the rows of a table.
+struct ListAccess<'de> {
+    list: &'de Any,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess<'de> {
+    type Error = ConversionError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, ConversionError> {
+        if self.index >= len(self.list) {
+            return Ok(None);
+        }
+        let item = Deserializer::item(self.list, self.index);
+        self.index += 1;
+        seed.deserialize(item).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(len(self.list) - self.index)
+    }
+}
+
+/// Reads the keys and values of a dictionary.
+struct DictAccess<'de> {
+    keys: &'de Any,
+    values: &'de Any,
+    index: usize,
+    len: usize,
+}
+
+impl<'de> DictAccess<'de> {
+    /// Fails if the values are not a list or table with one value for each key.
+    fn new(dict: &'de Any) -> Result<Self, ConversionError> {
+        let (keys, values) = unsafe {
+            let d = (*dict.k_ptr()).union.dict;
+            (&*(d.k as *const Any), &*(d.v as *const Any))
+        };
+        if values.k_type() != TABLE && values.list_len().is_none() {
+            return Err(ConversionError::UnsupportedType(format!(
+                "dictionary values of type {}",
+                values.k_type()
+            )));
+        }
+        if len(values) != len(keys) {
+            return Err(ConversionError::InvalidLength {
+                expected: len(keys),
+                found: len(values),
+            });
+        }
+        Ok(DictAccess {
+            keys,
+            values,
+            index: 0,
+            len: len(keys),
+        })
+    }
+}
+
+impl<'de> de::MapAccess<'de> for DictAccess<'de> {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ConversionError> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        seed.deserialize(Deserializer::item(self.keys, self.index)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ConversionError> {
+        let value = Deserializer::item(self.values, self.index);
+        self.index += 1;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+/// Reads a row of a table as a map of column names to values.
+struct RowAccess<'de> {
+    table: &'de Table,
+    row: usize,
+    column: usize,
+}
+
+impl<'de> de::MapAccess<'de> for RowAccess<'de> {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ConversionError> {
+        if self.column >= self.table.columns().len() {
+            return Ok(None);
+        }
+        seed.deserialize(Deserializer::item(self.table.column_names().as_ref(), self.column))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ConversionError> {
+        let column: &'de Any = &self.table.columns()[self.column];
+        self.column += 1;
+        seed.deserialize(Deserializer::item(column, self.row))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.table.columns().len() - self.column)
+    }
+}
+
+/// Reads an enum variant stored as a dictionary with a single key.
+struct VariantAccess<'de> {
+    variant: &'de str,
+    value: Deserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess<'de> {
+    type Error = ConversionError;
+    type Variant = Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Deserializer<'de>), ConversionError> {
+        let variant = seed.deserialize(IntoDeserializer::<ConversionError>::into_deserializer(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
+    type Error = ConversionError;
+
+    fn unit_variant(self) -> Result<(), ConversionError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, ConversionError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, ConversionError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+
+    use super::*;
+    use crate::{kapi, list, symbol, to_any, KBox};
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Trade {
+        sym: String,
+        price: f64,
+        size: Option<i64>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Message {
+        Heartbeat,
+        Quote(f64, f64),
+        Fill { id: i64 },
+        Cancel(i64),
+    }
+
+    #[test]
+    fn atoms_deserialize_to_primitives() {
+        assert_eq!(from_any::<i32>(KBox::new_atom(42i32).as_ref()).unwrap(), 42);
+        assert_eq!(from_any::<i64>(KBox::new_atom(42i32).as_ref()).unwrap(), 42);
+        assert_eq!(from_any::<f64>(KBox::new_atom(1.5f64).as_ref()).unwrap(), 1.5);
+        assert_eq!(
+            from_any::<String>(KBox::new_atom(symbol("abc")).as_ref()).unwrap(),
+            "abc"
+        );
+        assert_eq!(from_any::<&str>(KBox::new_atom(symbol("abc")).as_ref()).unwrap(), "abc");
+        assert!(from_any::<u8>(KBox::new_atom(-1i32).as_ref()).is_err());
+    }
+
+    #[test]
+    fn nulls_deserialize_to_none() {
+        assert_eq!(
+            from_any::<Option<i64>>(KBox::new_atom(i64::MIN).as_ref()).unwrap(),
+            None
+        );
+        assert_eq!(
+            from_any::<Option<f64>>(KBox::new_atom(f64::NAN).as_ref()).unwrap(),
+            None
+        );
+        assert_eq!(from_any::<Option<i64>>(KBox::new_atom(5i64).as_ref()).unwrap(), Some(5));
+        let l = list![i32; 1, i32::MIN, 3];
+        assert_eq!(
+            from_any::<Vec<Option<i32>>>(l.as_ref()).unwrap(),
+            vec![Some(1), None, Some(3)]
+        );
+    }
+
+    #[test]
+    fn lists_deserialize_to_sequences() {
+        let l = list![i64; 1, 2, 3];
+        assert_eq!(from_any::<Vec<i64>>(l.as_ref()).unwrap(), vec![1, 2, 3]);
+        let l = list![Any; 1i64, symbol("a")];
+        assert_eq!(from_any::<(i64, String)>(l.as_ref()).unwrap(), (1, "a".to_owned()));
+        let s = to_any("hello").unwrap();
+        assert_eq!(from_any::<String>(&s).unwrap(), "hello");
+        assert_eq!(from_any::<Vec<char>>(&s).unwrap(), vec!['h', 'e', 'l', 'l', 'o']);
+    }
+
+    #[test]
+    fn dictionaries_deserialize_to_maps() {
+        let mut dict = KBox::new_dict();
+        dict.insert(symbol("a"), 1i64);
+        dict.insert(symbol("b"), 2i64);
+        let map: HashMap<String, i64> = from_any(dict.as_ref()).unwrap();
+        assert_eq!(map["a"], 1);
+        assert_eq!(map["b"], 2);
+    }
+
+    #[test]
+    fn short_value_lists_are_rejected() {
+        let values: [KBox<Any>; 3] = [
+            list![i64; 1].into(),
+            list![Any; 1i64].into(),
+            KBox::new_atom(1i64).into(),
+        ];
+        for values in values {
+            let keys = list![Symbol; symbol("a"), symbol("b")];
+            let k: KBox<Any> = unsafe { KBox::from_raw(kapi::xD(keys.into_raw() as _, values.into_raw() as _) as _) };
+            assert!(from_any::<HashMap<String, i64>>(&k).is_err());
+            assert!(from_any::<Trade>(&k).is_err());
+        }
+        let keys = list![Symbol; symbol("a"), symbol("b")];
+        let k: KBox<Any> =
+            unsafe { KBox::from_raw(kapi::xD(keys.into_raw() as _, list![i64; 1].into_raw() as _) as _) };
+        assert!(matches!(
+            from_any::<HashMap<String, i64>>(&k),
+            Err(ConversionError::InvalidLength { expected: 2, found: 1 })
+        ));
+    }
+
+    #[test]
+    fn structs_round_trip() {
+        let trade = Trade {
+            sym: "abc".to_owned(),
+            price: 1.5,
+            size: None,
+        };
+        let k = to_any(&trade).unwrap();
+        assert_eq!(from_any::<Trade>(&k).unwrap(), trade);
+    }
+
+    #[test]
+    fn tables_deserialize_to_sequences_of_structs() {
+        let trades = vec![
+            Trade {
+                sym: "a".to_owned(),
+                price: 1.5,
+                size: Some(100),
+            },
+            Trade {
+                sym: "b".to_owned(),
+                price: 2.5,
+                size: None,
+            },
+        ];
+        let k = to_any(&trades).unwrap();
+        assert_eq!(k.k_type(), TABLE);
+        assert_eq!(from_any::<Vec<Trade>>(&k).unwrap(), trades);
+    }
+
+    #[test]
+    fn enums_round_trip() {
+        for message in [
+            Message::Heartbeat,
+            Message::Quote(1.0, 2.0),
+            Message::Fill { id: 7 },
+            Message::Cancel(3),
+        ] {
+            let k = to_any(&message).unwrap();
+            assert_eq!(from_any::<Message>(&k).unwrap(), message);
+        }
+    }
+}
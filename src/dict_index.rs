@@ -0,0 +1,231 @@
+//! Hash indexes over the keys of a dictionary, for repeated lookups.
+use crate::any::Any;
+use crate::dictionary::Dictionary;
+use crate::k_type::MIXED_LIST;
+use crate::kbox::KBox;
+use crate::list::{list_item, List};
+use crate::type_traits::KObject;
+use std::cell::OnceCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// A hash index of the keys of a dictionary, mapping the hash of each key to the positions it appears at.
+struct KeyIndex {
+    positions: HashMap<u64, Vec<usize>>,
+}
+
+fn hash_key(key: &Any) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes item `index` of a simple or mixed list, consistently with `hash_key`. Items of simple lists are hashed in
+/// place, rather than being copied into atoms.
+fn hash_item(keys: &Any, index: usize) -> u64 {
+    if keys.k_type() == MIXED_LIST {
+        let keys = unsafe { &*(keys as *const Any as *const List<Any>) };
+        hash_key(&keys[index])
+    } else {
+        let mut hasher = DefaultHasher::new();
+        unsafe { (*keys.k_ptr()).hash_item(index, &mut hasher) };
+        hasher.finish()
+    }
+}
+
+impl KeyIndex {
+    fn new(keys: &Any, unique: bool) -> Self {
+        let len = keys.list_len().unwrap_or(0);
+        let mut positions: HashMap<u64, Vec<usize>> = HashMap::with_capacity(len);
+        for i in 0..len {
+            let bucket = positions.entry(hash_item(keys, i)).or_default();
+            // Keys with the unique attribute can't repeat, so there is no need to look for an earlier copy.
+            if unique || !bucket.iter().any(|&j| items_eq(keys, i, j)) {
+                bucket.push(i);
+            }
+        }
+        KeyIndex { positions }
+    }
+
+    fn find(&self, keys: &Any, key: &Any) -> Option<usize> {
+        let bucket = self.positions.get(&hash_key(key))?;
+        bucket.iter().copied().find(|&i| item_eq(keys, i, key))
+    }
+}
+
+/// Returns true if item `index` of a simple or mixed list matches `key`.
+fn item_eq(keys: &Any, index: usize, key: &Any) -> bool {
+    if keys.k_type() == MIXED_LIST {
+        let keys = unsafe { &*(keys as *const Any as *const List<Any>) };
+        *keys[index] == *key
+    } else {
+        unsafe { (*keys.k_ptr()).item_matches(index, &*key.k_ptr()) }
+    }
+}
+
+/// Returns true if items `i` and `j` of a simple or mixed list match.
+fn items_eq(keys: &Any, i: usize, j: usize) -> bool {
+    if keys.k_type() == MIXED_LIST {
+        let keys = unsafe { &*(keys as *const Any as *const List<Any>) };
+        keys[i] == keys[j]
+    } else {
+        // Comparing the raw bytes is exact, so float keys that q matches, like 0.0 and -0.0, get separate entries.
+        // Lookups still find the first of them, since items are added in order.
+        unsafe { (*keys.k_ptr()).item_bytes(i) == (*keys.k_ptr()).item_bytes(j) }
+    }
+}
+
+/// A dictionary with a hash index of its keys, so that looking up a key takes O(1) time rather than the O(n) of
+/// `Dictionary::get`.
+///
+/// The index is built the first time a key is looked up, or when `build_index` is called. If the key list has the
+/// unique attribute (`u#`), q's sign that it will be searched by hash, the index is built straight away. Changing
+/// the dictionary through `dict_mut` drops the index, and it is rebuilt on the next lookup.
+///
+/// # Example
+/// ```
+/// use kdb::{parse_literal, cast, symbol, Dictionary, IndexedDictionary};
+///
+/// let d = cast!(parse_literal("`a`b`c!(1;2;3)").unwrap(); Dictionary);
+/// let d = IndexedDictionary::new(d);
+/// assert_eq!(d.index_of(symbol("b")), Some(1));
+/// ```
+pub struct IndexedDictionary {
+    dict: KBox<Dictionary>,
+    index: OnceCell<KeyIndex>,
+}
+
+impl IndexedDictionary {
+    /// Wraps a dictionary so that its keys can be looked up by hash.
+    pub fn new(dict: KBox<Dictionary>) -> Self {
+        let indexed = IndexedDictionary {
+            dict,
+            index: OnceCell::new(),
+        };
+        if unsafe { (*indexed.dict.keys_any().k_ptr()).attr().unique() } {
+            indexed.build_index();
+        }
+        indexed
+    }
+
+    /// Builds the index now, rather than on the first lookup. Does nothing if it has already been built.
+    pub fn build_index(&self) {
+        self.index();
+    }
+
+    fn index(&self) -> &KeyIndex {
+        self.index.get_or_init(|| {
+            let keys = self.dict.keys_any();
+            KeyIndex::new(keys, unsafe { (*keys.k_ptr()).attr().unique() })
+        })
+    }
+
+    /// The position of the first occurrence of a key in the key list. The keys can be a list of any type.
+    pub fn index_of<T: Into<KBox<Any>>>(&self, key: T) -> Option<usize> {
+        self.index().find(self.dict.keys_any(), &key.into())
+    }
+
+    /// Gets a value by key.
+    ///
+    /// # Panics
+    ///
+    /// Like `Dictionary::get`, this panics if the values are not a mixed list. Use `lookup` for dictionaries whose
+    /// values are a simple list.
+    pub fn get<T: Into<KBox<Any>>>(&self, key: T) -> Option<&KBox<Any>> {
+        let values = self.dict.values_to_borrow();
+        let index = self.index_of(key)?;
+        values.get(index)
+    }
+
+    /// Gets an owned reference to a value by key, whatever the type of the value list, in the same way as
+    /// `Dictionary::lookup`.
+    pub fn lookup<T: Into<KBox<Any>>>(&self, key: T) -> Option<KBox<Any>> {
+        let index = self.index_of(key)?;
+        let values = self.dict.values_any();
+        match values.k_type() {
+            t if t == MIXED_LIST || t.is_simple_list() => Some(unsafe { list_item(values.k_ptr(), index) }),
+            _ => None,
+        }
+    }
+
+    /// Gives mutable access to the dictionary. This drops the index, which is rebuilt on the next lookup.
+    pub fn dict_mut(&mut self) -> &mut Dictionary {
+        self.index.take();
+        &mut self.dict
+    }
+
+    /// Returns the dictionary, dropping the index.
+    pub fn into_inner(self) -> KBox<Dictionary> {
+        self.dict
+    }
+}
+
+impl Deref for IndexedDictionary {
+    type Target = Dictionary;
+
+    fn deref(&self) -> &Dictionary {
+        &self.dict
+    }
+}
+
+impl From<KBox<Dictionary>> for IndexedDictionary {
+    fn from(dict: KBox<Dictionary>) -> Self {
+        IndexedDictionary::new(dict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k::Attr;
+    use crate::{cast, parse_literal, symbol};
+
+    fn dict(s: &str) -> IndexedDictionary {
+        IndexedDictionary::new(cast!(parse_literal(s).unwrap(); Dictionary))
+    }
+
+    #[test]
+    fn looks_up_keys_of_any_type() {
+        let d = dict("`a`b`a!(1;`y;3)");
+        assert_eq!(d.index_of(symbol("a")), Some(0));
+        assert_eq!(d.index_of(symbol("b")), Some(1));
+        assert_eq!(d.index_of(symbol("c")), None);
+        assert_eq!(d.index_of(1), None);
+        assert_eq!(**d.get(symbol("b")).unwrap(), *parse_literal("`y").unwrap());
+
+        let d = dict("(1;`a;1.5)!(`x;`y;`z)");
+        assert_eq!(d.index_of(1.5), Some(2));
+        assert_eq!(d.index_of(1i32), None);
+
+        let d = dict("0n 1 2f!`x`y`z");
+        assert_eq!(d.index_of(f64::NAN), Some(0));
+        assert_eq!(d.index_of(2.0), Some(2));
+    }
+
+    #[test]
+    fn looks_up_simple_values() {
+        let d = dict("`a`b!1 2");
+        assert_eq!(*d.lookup(symbol("b")).unwrap(), *KBox::<Any>::from(2i64));
+        assert!(d.lookup(symbol("c")).is_none());
+    }
+
+    #[test]
+    fn unique_keys_are_indexed_straight_away() {
+        let d = cast!(parse_literal("`a`b!(1;2)").unwrap(); Dictionary);
+        unsafe { (*(*d.k_ptr()).union.dict.k).set_attr(Attr::UNIQUE) };
+        let d = IndexedDictionary::new(d);
+        assert!(d.index.get().is_some());
+        assert_eq!(d.index_of(symbol("b")), Some(1));
+        assert!(dict("`a`b!(1;2)").index.get().is_none());
+    }
+
+    #[test]
+    fn dict_mut_rebuilds_the_index() {
+        let mut d = dict("(`a;1)!(1;`b)");
+        assert_eq!(d.index_of(symbol("c")), None);
+        d.dict_mut().insert(symbol("c"), 3);
+        assert_eq!(d.index_of(symbol("c")), Some(2));
+    }
+}
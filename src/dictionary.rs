@@ -1,40 +1,92 @@
-use crate::list::List;
+use crate::compare::compare_item_with_atom;
+use crate::error::ConversionError;
+use crate::list::{
+    check_mixed_items, list_item, make_mixed, push_item, remove_item, replace_item, retain_items,
+    simple_list_from_atoms, List,
+};
+use crate::table::Table;
+use crate::type_traits::{private, KListable};
 use crate::{any::Any, k::K};
 use crate::{k_type::MIXED_LIST, kapi, type_traits::KObject};
 use crate::{
-    k_type::{KTypeCode, DICT},
+    k_type::{KTypeCode, DICT, GENERIC_NULL, TABLE},
     kbox::KBox,
     type_traits::KTyped,
 };
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
 use std::{mem, ops::Index};
 
 /// A key value based dictionary.
+///
+/// The keys and values of a `Dictionary` can be lists of any type. The `keys` and `values` methods view them as
+/// mixed lists, so use `keys_any` and `values_any`, or cast to a typed `Dict`, for dictionaries whose keys or
+/// values are simple lists, such as a symbol list.
 #[repr(transparent)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct Dictionary {
     k: K,
 }
 
 impl Dictionary {
-    fn key_list_mut(&mut self) -> &mut KBox<List<Any>> {
-        unsafe { &mut *(&mut self.k.union.dict.k as *mut _ as *mut KBox<List<Any>>) }
+    /// The key and value lists, which can be reallocated.
+    fn lists_mut(&mut self) -> (&mut *mut K, &mut *mut K) {
+        for (list, name) in [(self.keys_any(), "keys"), (self.values_any(), "values")] {
+            assert!(
+                list.list_len().is_some(),
+                "dictionary {} are a {}, not a list",
+                name,
+                list.k_type()
+            );
+        }
+        unsafe { (&mut self.k.union.dict.k, &mut self.k.union.dict.v) }
     }
 
+    /// The value list as a mixed list. Simple value lists are converted into a mixed list first, so that each value
+    /// can be borrowed mutably.
     fn value_list_mut(&mut self) -> &mut KBox<List<Any>> {
-        unsafe { &mut *(&mut self.k.union.dict.v as *mut _ as *mut KBox<List<Any>>) }
+        let values = self.lists_mut().1;
+        unsafe {
+            if (**values).t != MIXED_LIST {
+                make_mixed(values);
+            }
+            &mut *(values as *mut *mut K as *mut KBox<List<Any>>)
+        }
     }
 
     fn key_list(&self) -> &KBox<List<Any>> {
+        assert_mixed(self.keys_any(), "keys");
         unsafe { &*(&self.k.union.dict.k as *const _ as *const KBox<List<Any>>) }
     }
 
     fn value_list(&self) -> &KBox<List<Any>> {
+        assert_mixed(self.values_any(), "values");
         unsafe { &*(&self.k.union.dict.v as *const _ as *const KBox<List<Any>>) }
     }
 
+    /// The keys of the dictionary, whatever the type of the key list. For a keyed table this is a table.
+    #[inline]
+    pub fn keys_any(&self) -> &Any {
+        unsafe { &*(self.k.union.dict.k as *const Any) }
+    }
+
+    /// The values of the dictionary, whatever the type of the value list.
+    #[inline]
+    pub fn values_any(&self) -> &Any {
+        unsafe { &*(self.k.union.dict.v as *const Any) }
+    }
+
     /// The number of items in the dictionary.
     #[inline]
     pub fn len(&self) -> usize {
-        self.key_list().len()
+        let keys = self.keys_any();
+        match keys.k_type() {
+            TABLE => unsafe { &*(keys as *const Any as *const Table) }.len(),
+            _ => keys.list_len().unwrap_or(0),
+        }
     }
 
     /// Returns true if the dictionary has no items.
@@ -44,12 +96,20 @@ impl Dictionary {
     }
 
     /// Gets a slice containing all the keys in this dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not a mixed list. Use `keys_any` for other dictionaries.
     #[inline]
     pub fn keys(&self) -> &[KBox<Any>] {
         &self.key_list()[..]
     }
 
     /// Gets a slice containing all the values in this dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not a mixed list. Use `values_any` for other dictionaries.
     #[inline]
     pub fn values(&self) -> &[KBox<Any>] {
         &self.value_list()[..]
@@ -57,32 +117,356 @@ impl Dictionary {
 
     /// Insert a specified key and value at the end of the dictionary.
     /// No checks are done on uniqueness so duplicates are possible.
+    ///
+    /// If the keys or values are a simple list and the new item is not an atom of the same type, that list is
+    /// converted into a mixed list, as q does when joining.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys or values are not lists, as in a keyed table.
     #[inline]
     pub fn insert(&mut self, key: impl Into<KBox<Any>>, value: impl Into<KBox<Any>>) {
-        self.key_list_mut().push(key.into());
-        self.value_list_mut().push(value.into());
+        let (keys, values) = self.lists_mut();
+        unsafe {
+            push_item(keys, key.into());
+            push_item(values, value.into());
+        }
+    }
+
+    /// Sets the value of a key, replacing the existing value if the key is already in the dictionary, or adding
+    /// the key at the end otherwise. Returns the old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys or values are not lists.
+    pub fn insert_or_replace(&mut self, key: impl Into<KBox<Any>>, value: impl Into<KBox<Any>>) -> Option<KBox<Any>> {
+        let key = key.into();
+        match self.position(&key) {
+            Some(index) => Some(unsafe { replace_item(self.lists_mut().1, index, value.into()) }),
+            None => {
+                self.insert(key, value);
+                None
+            }
+        }
+    }
+
+    /// Removes a key from the dictionary, returning its value if it was present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys or values are not lists.
+    pub fn remove<T: Into<KBox<Any>>>(&mut self, key: T) -> Option<KBox<Any>> {
+        let index = self.position(&key.into())?;
+        let (keys, values) = self.lists_mut();
+        unsafe {
+            remove_item(*keys, index);
+            Some(remove_item(*values, index))
+        }
+    }
+
+    /// Keeps only the items for which `f` returns true. Items of simple lists are passed to `f` as atoms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys or values are not lists.
+    pub fn retain(&mut self, mut f: impl FnMut(&Any, &Any) -> bool) {
+        let (keys, values) = self.lists_mut();
+        let (keys, values) = (*keys, *values);
+        unsafe {
+            let keep: Vec<bool> = (0..(*keys).union.list.n as usize)
+                .map(|i| f(&list_item(keys, i), &list_item(values, i)))
+                .collect();
+            retain_items(keys, |i| keep[i]);
+            retain_items(values, |i| keep[i]);
+        }
+    }
+
+    /// Gets a mutable reference to a value by key. If the key is found and the values are a simple list, they are
+    /// converted into a mixed list first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys or values are not lists.
+    pub fn get_mut<T: Into<KBox<Any>>>(&mut self, key: T) -> Option<&mut KBox<Any>> {
+        let index = self.position(&key.into())?;
+        self.value_list_mut().get_mut(index)
+    }
+
+    /// Gets the entry for a key, so that it can be inserted or updated in place, in the same way as
+    /// `HashMap::entry`. If the values are a simple list, they are converted into a mixed list first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys or values are not lists.
+    ///
+    /// # Example
+    /// ```
+    /// use kdb::{cast, symbol, Any, Atom, KBox};
+    ///
+    /// let mut d = KBox::new_dict();
+    /// for word in &["a", "b", "a"] {
+    ///     let count = d.entry(symbol(word)).or_insert(0i64);
+    ///     let n = cast!(&**count; Atom<i64>).value();
+    ///     *count = KBox::<Any>::from(n + 1);
+    /// }
+    /// assert_eq!(**d.get(symbol("a")).unwrap(), *KBox::<Any>::from(2i64));
+    /// ```
+    pub fn entry(&mut self, key: impl Into<KBox<Any>>) -> Entry<'_> {
+        self.value_list_mut();
+        let key = key.into();
+        match self.position(&key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { dict: self, index }),
+            None => Entry::Vacant(VacantEntry { dict: self, key }),
+        }
     }
 
-    /// Gets a value by key. Note that KDB dictionaries are treated as unordered and hence this is an O(n) operation.
+    /// Gets a value by key. Note that KDB dictionaries are treated as unordered and hence this is an O(n) operation,
+    /// unless the keys have the sorted attribute, when it is O(log n). Use an `IndexedDictionary` for repeated
+    /// lookups.
+    ///
+    /// The keys can be a list of any type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not a mixed list, as the values of a simple list aren't stored as K objects that can
+    /// be borrowed. Use `lookup` for those dictionaries.
     #[inline]
     pub fn get<T: Into<KBox<Any>>>(&self, key: T) -> Option<&KBox<Any>> {
-        let key = key.into();
-        let index = self
-            .keys()
-            .iter()
-            .enumerate()
-            .find(|(_, k2)| unsafe { *k2.k.as_ptr() == *key.k.as_ptr() })
-            .map(|(i, _)| i)?;
-        self.values().get(index)
+        let values = self.values_to_borrow();
+        let index = self.position(&key.into())?;
+        values.get(index)
+    }
+
+    /// The values as a slice, for the methods that return a borrowed value.
+    pub(crate) fn values_to_borrow(&self) -> &[KBox<Any>] {
+        let values = self.values_any();
+        assert!(
+            values.k_type() == MIXED_LIST,
+            "dictionary values are a {}, not a mixed list; use lookup to get values from a simple list",
+            values.k_type()
+        );
+        self.values()
     }
 
-    /// An iterator through every value in the KDB object
+    /// Gets an owned reference to a value by key, whatever the type of the value list. Values from a simple list
+    /// are copied into a new atom, and values from a mixed list have their reference count incremented.
+    ///
+    /// # Example
+    /// ```
+    /// use kdb::{cast, parse_literal, symbol, Any, Dictionary, KBox};
+    ///
+    /// let d = cast!(parse_literal("`a`b!1 2").unwrap(); Dictionary);
+    /// assert_eq!(*d.lookup(symbol("b")).unwrap(), *KBox::<Any>::from(2i64));
+    /// assert!(d.lookup(symbol("c")).is_none());
+    /// ```
+    pub fn lookup<T: Into<KBox<Any>>>(&self, key: T) -> Option<KBox<Any>> {
+        let index = self.position(&key.into())?;
+        let values = self.values_any();
+        match values.k_type() {
+            t if t == MIXED_LIST || t.is_simple_list() => Some(unsafe { list_item(values.k_ptr(), index) }),
+            _ => None,
+        }
+    }
+
+    /// The index of a key in the key list.
+    fn position(&self, key: &Any) -> Option<usize> {
+        let keys = self.keys_any();
+        match keys.k_type() {
+            MIXED_LIST => self.keys().iter().position(|k| **k == *key),
+            t if t.is_simple_list() && key.k_type() == t.as_atom() => {
+                // the items are compared in place, rather than being copied into atoms.
+                let (list, key) = unsafe { (&*keys.k_ptr(), &*key.k_ptr()) };
+                let matches = |i| unsafe { list.item_matches(i, key) };
+                if list.attr().sorted() {
+                    // sorted keys can be binary searched for the first match.
+                    let (mut low, mut high) = (0, self.len());
+                    while low < high {
+                        let mid = (low + high) / 2;
+                        if unsafe { compare_item_with_atom(list, mid, key) } == Ordering::Less {
+                            low = mid + 1;
+                        } else {
+                            high = mid;
+                        }
+                    }
+                    Some(low).filter(|&i| i < self.len() && matches(i))
+                } else {
+                    (0..self.len()).position(matches)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// An iterator through every value in the KDB object. The keys and values must be mixed lists.
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = (&KBox<Any>, &KBox<Any>)> {
         self.keys().iter().zip(self.values().iter())
     }
 }
 
+/// An entry in a dictionary, which may or may not hold a value. Returned by `Dictionary::entry`.
+pub enum Entry<'a> {
+    /// The key is in the dictionary.
+    Occupied(OccupiedEntry<'a>),
+    /// The key is not in the dictionary.
+    Vacant(VacantEntry<'a>),
+}
+
+/// An entry for a key that is in a dictionary.
+pub struct OccupiedEntry<'a> {
+    dict: &'a mut Dictionary,
+    index: usize,
+}
+
+/// An entry for a key that is not in a dictionary.
+pub struct VacantEntry<'a> {
+    dict: &'a mut Dictionary,
+    key: KBox<Any>,
+}
+
+impl<'a> Entry<'a> {
+    /// The key of the entry. Keys from a simple list are copied into an atom.
+    pub fn key(&self) -> KBox<Any> {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key().clone(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns a mutable reference to the value.
+    pub fn or_insert(self, default: impl Into<KBox<Any>>) -> &'a mut KBox<Any> {
+        self.or_insert_with(|| default.into())
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns a mutable reference to the value.
+    pub fn or_insert_with<F: FnOnce() -> KBox<Any>>(self, default: F) -> &'a mut KBox<Any> {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// Calls `f` with the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut KBox<Any>)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(e) = &mut self {
+            f(e.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    /// The key of the entry. Keys from a simple list are copied into an atom.
+    pub fn key(&self) -> KBox<Any> {
+        unsafe { list_item(self.dict.keys_any().k_ptr(), self.index) }
+    }
+
+    /// The value of the entry.
+    pub fn get(&self) -> &KBox<Any> {
+        &self.dict.values()[self.index]
+    }
+
+    /// A mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut KBox<Any> {
+        &mut self.dict.value_list_mut()[self.index]
+    }
+
+    /// Converts the entry into a mutable reference to its value, with the lifetime of the dictionary.
+    pub fn into_mut(self) -> &'a mut KBox<Any> {
+        &mut self.dict.value_list_mut()[self.index]
+    }
+
+    /// Replaces the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: impl Into<KBox<Any>>) -> KBox<Any> {
+        mem::replace(self.get_mut(), value.into())
+    }
+
+    /// Removes the entry from the dictionary, returning its value.
+    pub fn remove(self) -> KBox<Any> {
+        let (keys, values) = self.dict.lists_mut();
+        unsafe {
+            remove_item(*keys, self.index);
+            remove_item(*values, self.index)
+        }
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    /// The key that would be inserted.
+    pub fn key(&self) -> &KBox<Any> {
+        &self.key
+    }
+
+    /// Adds the key and value to the end of the dictionary, returning a mutable reference to the value.
+    pub fn insert(self, value: impl Into<KBox<Any>>) -> &'a mut KBox<Any> {
+        let index = self.dict.len();
+        self.dict.insert(self.key, value);
+        &mut self.dict.value_list_mut()[index]
+    }
+}
+
+impl<Key: Into<KBox<Any>>, Value: Into<KBox<Any>>> Extend<(Key, Value)> for KBox<Dictionary> {
+    /// Inserts each key and value, replacing the values of keys that are already in the dictionary.
+    fn extend<I: IntoIterator<Item = (Key, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert_or_replace(key, value);
+        }
+    }
+}
+
+/// Collects keys and values into a dictionary. If a key appears more than once, the last value is kept.
+/// When every key is an atom of the same type, the keys are stored in a simple list of that type, such as a symbol
+/// list; otherwise they are stored in a mixed list. The values are always stored in a mixed list.
+impl<Key: Into<KBox<Any>>, Value: Into<KBox<Any>>> FromIterator<(Key, Value)> for KBox<Dictionary> {
+    fn from_iter<I: IntoIterator<Item = (Key, Value)>>(iter: I) -> Self {
+        let mut keys: Vec<KBox<Any>> = Vec::new();
+        let mut values: Vec<KBox<Any>> = Vec::new();
+        let mut positions: HashMap<KBox<Any>, usize> = HashMap::new();
+        for (key, value) in iter {
+            let (key, value) = (key.into(), value.into());
+            match positions.get(&key) {
+                Some(&i) => values[i] = value,
+                None => {
+                    positions.insert(key.clone(), keys.len());
+                    keys.push(key);
+                    values.push(value);
+                }
+            }
+        }
+        let simple_keys = if keys.iter().any(|k| k.k_type() == GENERIC_NULL) {
+            None
+        } else {
+            simple_list_from_atoms(&keys)
+        };
+        let keys: KBox<Any> = simple_keys.unwrap_or_else(|| keys.into_iter().collect::<KBox<List<Any>>>().into());
+        let values: KBox<List<Any>> = values.into_iter().collect();
+        unsafe {
+            let dict = kapi::xD(keys.into_raw() as *const K, values.into_raw() as *const K);
+            KBox::from_raw(dict as *mut K)
+        }
+    }
+}
+
+/// A list of dictionaries is stored as a mixed list.
+impl KListable for Dictionary {
+    const LIST_TYPE_CODE: KTypeCode = MIXED_LIST;
+    type ListItem = KBox<Dictionary>;
+
+    unsafe fn join_to(item: Self::ListItem, mut k: *mut K) -> *mut K {
+        kapi::jk(&mut k, item.into_raw() as *const K)
+    }
+
+    fn check_items(list: &K) -> Result<(), ConversionError> {
+        check_mixed_items::<Dictionary>(list)
+    }
+}
+
+/// Gets a value by key.
+///
+/// # Panics
+///
+/// Panics if the key isn't in the dictionary, or if the values are not a mixed list. Use `lookup` for
+/// dictionaries whose values are a simple list.
 impl<T> Index<T> for Dictionary
 where
     for<'a> T: Into<KBox<Any>>,
@@ -90,7 +474,7 @@ where
     type Output = Any;
 
     fn index(&self, index: T) -> &Self::Output {
-        self.get(index).unwrap()
+        self.get(index).expect("key is not in the dictionary")
     }
 }
 
@@ -114,16 +498,218 @@ impl KBox<Dictionary> {
     /// Create a new empty dictionary.
     pub fn new_dict() -> Self {
         unsafe {
-            let keys = kapi::ktn(MIXED_LIST.into(), 0) as *mut K;
-            let values = kapi::ktn(MIXED_LIST.into(), 0) as *mut K;
+            let keys = kapi::ktn(MIXED_LIST.into(), 0);
+            let values = kapi::ktn(MIXED_LIST.into(), 0);
             mem::transmute(kapi::xD(keys, values))
         }
     }
 }
 
+fn assert_mixed(list: &Any, name: &str) {
+    assert!(
+        list.k_type() == MIXED_LIST,
+        "dictionary {} are a {}, not a mixed list",
+        name,
+        list.k_type()
+    );
+}
+
+/// A dictionary with typed keys and values, such as a `Dict<Symbol, f64>` that maps a symbol list to a float list.
+/// It has the same layout as a `Dictionary`, and `try_cast!` checks the types of both lists.
+///
+/// # Example
+/// ```
+/// use kdb::{cast, parse_literal, symbol, Dict, Symbol};
+///
+/// let k = parse_literal("`a`b!1.5 2.5").unwrap();
+/// let d = cast!(k; Dict<Symbol, f64>);
+/// assert_eq!(d.get(&symbol("b")), Some(&2.5));
+/// ```
+#[repr(transparent)]
+pub struct Dict<Key, Value> {
+    k: K,
+    _p: PhantomData<(Key, Value)>,
+}
+
+impl<Key: KListable, Value: KListable> Dict<Key, Value> {
+    fn key_list_mut(&mut self) -> &mut KBox<List<Key>> {
+        unsafe { &mut *(&mut self.k.union.dict.k as *mut _ as *mut KBox<List<Key>>) }
+    }
+
+    fn value_list_mut(&mut self) -> &mut KBox<List<Value>> {
+        unsafe { &mut *(&mut self.k.union.dict.v as *mut _ as *mut KBox<List<Value>>) }
+    }
+
+    /// The list of keys.
+    #[inline]
+    pub fn keys(&self) -> &List<Key> {
+        unsafe { &*(self.k.union.dict.k as *const List<Key>) }
+    }
+
+    /// The list of values.
+    #[inline]
+    pub fn values(&self) -> &List<Value> {
+        unsafe { &*(self.k.union.dict.v as *const List<Value>) }
+    }
+
+    /// The list of values, which can be changed in place.
+    #[inline]
+    pub fn values_mut(&mut self) -> &mut List<Value> {
+        unsafe { &mut *(self.k.union.dict.v as *mut List<Value>) }
+    }
+
+    /// The number of items in the dictionary.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    /// Returns true if the dictionary has no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert a specified key and value at the end of the dictionary.
+    /// No checks are done on uniqueness so duplicates are possible.
+    pub fn insert(&mut self, key: Key::ListItem, value: Value::ListItem) {
+        self.key_list_mut().push(key);
+        self.value_list_mut().push(value);
+    }
+
+    /// Gets a value by key. This is an O(n) operation.
+    pub fn get(&self, key: &Key::ListItem) -> Option<&Value::ListItem>
+    where
+        Key::ListItem: PartialEq,
+    {
+        let index = self.keys().iter().position(|k| k == key)?;
+        self.values().get(index)
+    }
+
+    /// Gets a mutable reference to a value by key. This is an O(n) operation.
+    pub fn get_mut(&mut self, key: &Key::ListItem) -> Option<&mut Value::ListItem>
+    where
+        Key::ListItem: PartialEq,
+    {
+        let index = self.keys().iter().position(|k| k == key)?;
+        self.values_mut().get_mut(index)
+    }
+
+    /// An iterator through every key and value in the dictionary.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key::ListItem, &Value::ListItem)> {
+        self.keys().iter().zip(self.values().iter())
+    }
+}
+
+impl<Key, Value> KObject for Dict<Key, Value> {
+    #[inline]
+    fn k_ptr(&self) -> *const K {
+        &self.k
+    }
+
+    #[inline]
+    fn k_ptr_mut(&mut self) -> *mut K {
+        &mut self.k
+    }
+}
+
+impl<Key, Value> private::Sealed for Dict<Key, Value> {}
+
+impl<Key: KListable, Value: KListable> KTyped for Dict<Key, Value> {
+    const K_TYPE: KTypeCode = DICT;
+
+    fn check_type(k: &K) -> Result<(), ConversionError> {
+        if k.t != DICT {
+            return Err(ConversionError::InvalidKCast { from: k.t, to: DICT });
+        }
+        unsafe {
+            List::<Key>::check_type(&*k.union.dict.k)?;
+            List::<Value>::check_type(&*k.union.dict.v)
+        }
+    }
+}
+
+/// A list of typed dictionaries is stored as a mixed list.
+impl<Key: KListable, Value: KListable> KListable for Dict<Key, Value> {
+    const LIST_TYPE_CODE: KTypeCode = MIXED_LIST;
+    type ListItem = KBox<Dict<Key, Value>>;
+
+    unsafe fn join_to(item: Self::ListItem, mut k: *mut K) -> *mut K {
+        kapi::jk(&mut k, item.into_raw() as *const K)
+    }
+
+    fn check_items(list: &K) -> Result<(), ConversionError> {
+        check_mixed_items::<Dict<Key, Value>>(list)
+    }
+}
+
+impl<Key, Value> PartialEq for Dict<Key, Value> {
+    fn eq(&self, other: &Self) -> bool {
+        self.k == other.k
+    }
+}
+
+impl<Key, Value> Eq for Dict<Key, Value> {}
+
+impl<Key, Value> Hash for Dict<Key, Value> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.k.hash(state)
+    }
+}
+
+impl<Key, Value> AsRef<Dictionary> for Dict<Key, Value> {
+    fn as_ref(&self) -> &Dictionary {
+        unsafe { &*(self as *const _ as *const _) }
+    }
+}
+
+impl<Key, Value> AsRef<Any> for Dict<Key, Value> {
+    fn as_ref(&self) -> &Any {
+        unsafe { &*(self as *const _ as *const _) }
+    }
+}
+
+impl<Key, Value> From<KBox<Dict<Key, Value>>> for KBox<Dictionary> {
+    fn from(value: KBox<Dict<Key, Value>>) -> Self {
+        unsafe { mem::transmute(value) }
+    }
+}
+
+impl<Key, Value> From<KBox<Dict<Key, Value>>> for KBox<Any> {
+    fn from(value: KBox<Dict<Key, Value>>) -> Self {
+        unsafe { mem::transmute(value) }
+    }
+}
+
+impl<Key: KListable, Value: KListable> KBox<Dict<Key, Value>> {
+    /// Create a new empty dictionary with typed key and value lists.
+    pub fn new_typed_dict() -> Self {
+        unsafe {
+            let keys = kapi::ktn(Key::LIST_TYPE_CODE.into(), 0);
+            let values = kapi::ktn(Value::LIST_TYPE_CODE.into(), 0);
+            mem::transmute(kapi::xD(keys, values))
+        }
+    }
+
+    /// Creates a dictionary from a list of keys and a list of values, which must be the same length.
+    pub fn from_lists(keys: KBox<List<Key>>, values: KBox<List<Value>>) -> Result<Self, ConversionError> {
+        if keys.len() != values.len() {
+            return Err(ConversionError::InvalidLength {
+                expected: keys.len(),
+                found: values.len(),
+            });
+        }
+        unsafe {
+            let dict = kapi::xD(keys.into_raw() as *const K, values.into_raw() as *const K);
+            Ok(KBox::from_raw(dict as *mut K))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::symbol;
+    use crate::literal::literal;
+    use crate::{cast, list, parse_literal, symbol, try_cast, Symbol};
 
     use super::*;
 
@@ -144,4 +730,169 @@ mod tests {
 
         assert_eq!(*val.as_ref(), *KBox::<Any>::from(symbol("World")).as_ref());
     }
+
+    #[test]
+    fn keys_any_views_typed_key_lists() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        let d = cast!(d; Dictionary);
+        assert_eq!(d.len(), 2);
+        assert_eq!(
+            cast!(d.keys_any(); List<Symbol>).as_slice(),
+            &[symbol("a"), symbol("b")]
+        );
+        assert_eq!(cast!(d.values_any(); List<i64>).as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a mixed list")]
+    fn keys_panics_for_typed_key_lists() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        cast!(d; Dictionary).keys();
+    }
+
+    #[test]
+    fn get_finds_keys_in_simple_lists() {
+        let d = parse_literal("`a`b!(1;\"x\")").unwrap();
+        let d = cast!(d; Dictionary);
+        assert_eq!(**d.get(symbol("b")).unwrap(), *parse_literal("\"x\"").unwrap());
+        assert!(d.get(symbol("c")).is_none());
+        assert!(d.get(1).is_none());
+    }
+
+    #[test]
+    fn lookup_works_for_any_value_list() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        let d = cast!(d; Dictionary);
+        assert_eq!(*d.lookup(symbol("b")).unwrap(), *KBox::<Any>::from(2i64));
+        assert!(d.lookup(symbol("c")).is_none());
+
+        let d = parse_literal("`a`b!(1;\"x\")").unwrap();
+        let d = cast!(d; Dictionary);
+        let value = d.lookup(symbol("b")).unwrap();
+        assert_eq!(value.k_ptr(), d.get(symbol("b")).unwrap().k_ptr());
+    }
+
+    #[test]
+    #[should_panic(expected = "use lookup")]
+    fn get_panics_for_simple_value_lists() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        cast!(d; Dictionary).get(symbol("c"));
+    }
+
+    #[test]
+    #[should_panic(expected = "use lookup")]
+    fn index_panics_for_simple_value_lists() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        let _ = &cast!(d; Dictionary)[symbol("a")];
+    }
+
+    #[test]
+    fn entry_and_get_mut_convert_simple_values() {
+        let d = parse_literal("`a`b!1 2").unwrap();
+        let mut d = cast!(d; Dictionary);
+        assert!(d.get_mut(symbol("c")).is_none());
+        assert_eq!(d.values_any().k_type(), crate::k_type::LONG_LIST);
+
+        *d.get_mut(symbol("a")).unwrap() = KBox::<Any>::from(symbol("x"));
+        *d.entry(symbol("c")).or_insert(3i64) = KBox::<Any>::from(4i64);
+        *d.entry(symbol("b")).or_insert(0i64) = KBox::<Any>::from(5i64);
+        assert_eq!(*d.values_any(), *parse_literal("(`x;5;4)").unwrap());
+
+        let d = parse_literal("`a`b!1 2").unwrap();
+        let mut d = cast!(d; Dictionary);
+        match d.entry(symbol("c")) {
+            Entry::Vacant(e) => *e.insert(3i64) = KBox::<Any>::from(6i64),
+            Entry::Occupied(_) => panic!("c is not in the dictionary"),
+        }
+        assert_eq!(d.len(), 3);
+        assert_eq!(**d.get(symbol("c")).unwrap(), *KBox::<Any>::from(6i64));
+    }
+
+    #[test]
+    fn get_binary_searches_sorted_keys() {
+        let mut keys = list![i64; 5, 1, 3, 3];
+        keys.sort();
+        let values: KBox<List<Any>> = [10, 20, 30, 40].iter().map(|&v| KBox::<Any>::from(v)).collect();
+        let d: KBox<Dictionary> = KBox::<Dict<i64, Any>>::from_lists(keys, values).unwrap().into();
+        assert_eq!(**d.get(3i64).unwrap(), *KBox::<Any>::from(20));
+        assert_eq!(**d.get(5i64).unwrap(), *KBox::<Any>::from(40));
+        assert!(d.get(4i64).is_none());
+        assert!(d.get(6i64).is_none());
+    }
+
+    #[test]
+    fn typed_dicts_check_key_and_value_types() {
+        let k = parse_literal("`a`b!1.5 2.5").unwrap();
+        assert!(try_cast!(&k; Dict<Symbol, i64>).is_err());
+        assert!(try_cast!(&k; Dict<i64, f64>).is_err());
+        let mut d = cast!(k; Dict<Symbol, f64>);
+        d.insert(symbol("c"), 3.5);
+        *d.get_mut(&symbol("a")).unwrap() = 0.5;
+        assert_eq!(d.values().as_slice(), &[0.5, 2.5, 3.5]);
+        assert_eq!(d.get(&symbol("c")), Some(&3.5));
+
+        let d = KBox::<Dict<Symbol, f64>>::from_lists(list![Symbol; symbol("x")], list![f64; 1.0]).unwrap();
+        let d: KBox<Dictionary> = d.into();
+        assert_eq!(d.len(), 1);
+        assert!(KBox::<Dict<Symbol, f64>>::from_lists(list![Symbol; symbol("x")], KBox::new_list()).is_err());
+
+        let mut d = KBox::<Dict<i32, Symbol>>::new_typed_dict();
+        d.insert(1, symbol("one"));
+        assert_eq!(d.iter().collect::<Vec<_>>(), vec![(&1, &symbol("one"))]);
+    }
+
+    #[test]
+    fn insert_or_replace_updates_existing_keys() {
+        let mut d = cast!(literal("`a`b!1 2"); Dictionary);
+        assert_eq!(d.insert_or_replace(symbol("b"), 20i64), Some(literal("2")));
+        assert_eq!(d.insert_or_replace(symbol("c"), 3i64), None);
+        assert_eq!(KBox::<Any>::from(d), literal("`a`b`c!1 20 3"));
+
+        let mut d = cast!(literal("`a`b!1 2"); Dictionary);
+        d.insert_or_replace(symbol("a"), symbol("x"));
+        d.insert_or_replace(1i64, 3i64);
+        assert_eq!(KBox::<Any>::from(d), literal("(`a;`b;1)!(`x;2;3)"));
+    }
+
+    #[test]
+    fn remove_and_retain_drop_items() {
+        let mut d = cast!(literal("`a`b`c`d!(1;`x;3;\"y\")"); Dictionary);
+        assert_eq!(d.remove(symbol("b")), Some(literal("`x")));
+        assert_eq!(d.remove(symbol("b")), None);
+        d.retain(|k, _| *k != *literal("`c"));
+        assert_eq!(KBox::<Any>::from(d), literal("`a`d!(1;\"y\")"));
+    }
+
+    #[test]
+    fn entry_inserts_or_updates_values() {
+        let mut d = KBox::new_dict();
+        d.entry(symbol("a")).or_insert(1i64);
+        *d.entry(symbol("a")).or_insert(5i64) = KBox::from(2i64);
+        d.entry(symbol("b"))
+            .and_modify(|v| *v = KBox::from(0i64))
+            .or_insert_with(|| KBox::from(3i64));
+        match d.entry(symbol("b")) {
+            Entry::Occupied(mut e) => {
+                assert_eq!(e.key(), literal("`b"));
+                assert_eq!(e.insert(4i64), literal("3"));
+                assert_eq!(e.remove(), literal("4"));
+            }
+            Entry::Vacant(_) => panic!("b should be present"),
+        }
+        assert_eq!(d.len(), 1);
+        assert_eq!(**d.get_mut(symbol("a")).unwrap(), *literal("2"));
+    }
+
+    #[test]
+    fn collect_picks_a_simple_key_list() {
+        let d: KBox<Dictionary> = vec![(symbol("a"), 1i64), (symbol("b"), 2i64), (symbol("a"), 3i64)]
+            .into_iter()
+            .collect();
+        assert_eq!(d.to_string(), "`a`b!(3;2)");
+        let keys = vec![KBox::<Any>::from(1i64), KBox::from(symbol("x"))];
+        let mut d: KBox<Dictionary> = keys.into_iter().zip(vec![1i64, 2]).collect();
+        assert_eq!(d.keys_any().k_type(), MIXED_LIST);
+        d.extend(vec![(symbol("x"), 5i64), (symbol("y"), 6i64)]);
+        assert_eq!(d.to_string(), "(1;`x;`y)!(1;5;6)");
+    }
 }
@@ -19,6 +19,57 @@ pub enum ConversionError {
     /// Symbol is not a valid rust string (not UTF-8)
     #[error("Symbol not a valid Rust string")]
     InvalidString,
+    /// A dictionary did not contain a key required to build a Rust value.
+    #[error("Missing key `{0}`")]
+    MissingKey(String),
+    /// The value stored under a dictionary key could not be converted.
+    #[error("Invalid value for key `{key}`: {source}")]
+    InvalidValue {
+        /// The key of the value that failed to convert
+        key: String,
+        /// The reason the conversion failed
+        source: Box<ConversionError>,
+    },
+    /// An item in a list could not be converted.
+    #[error("Invalid item at index {index}: {source}")]
+    InvalidItem {
+        /// The index of the item that failed to convert
+        index: usize,
+        /// The reason the conversion failed
+        source: Box<ConversionError>,
+    },
+    /// A list did not have the number of items required.
+    #[error("Expected a list of length {expected}, found length {found}")]
+    InvalidLength {
+        /// The length required
+        expected: usize,
+        /// The length of the list
+        found: usize,
+    },
+    /// A symbol did not name any variant of the enum being converted to.
+    #[error("Unknown variant `{0}`")]
+    UnknownVariant(String),
+    /// The type of a value has no equivalent in the type being converted to.
+    #[error("Unsupported type: {0}")]
+    UnsupportedType(String),
+    /// A value is outside the range that can be represented by the type being converted to.
+    #[error("Value out of range")]
+    OutOfRange,
+    /// A null value has no equivalent in the type being converted to.
+    #[error("Null value")]
+    NullValue,
+    /// Any other conversion error.
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl ConversionError {
+    pub(crate) fn at_index(index: usize, source: ConversionError) -> ConversionError {
+        ConversionError::InvalidItem {
+            index,
+            source: Box::new(source),
+        }
+    }
 }
 
 impl From<Utf8Error> for ConversionError {
@@ -27,6 +78,33 @@ impl From<Utf8Error> for ConversionError {
     }
 }
 
+/// Error type for parsing q literals, such as `2020.02.12D10:15:00.000000000`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind} at position {position}")]
+pub struct ParseError {
+    /// The byte offset into the input at which the error was found.
+    pub position: usize,
+    /// The reason parsing failed.
+    pub kind: ParseErrorKind,
+}
+
+/// The reason a q literal could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseErrorKind {
+    /// A different character (or the end of the input) was found where the described item was expected.
+    #[error("expected {0}")]
+    Expected(&'static str),
+    /// A component of the literal is outside its valid range, such as a month of 13.
+    #[error("value out of range")]
+    OutOfRange,
+    /// The items of a dictionary or the columns of a table have different lengths.
+    #[error("mismatched lengths")]
+    Length,
+    /// The literal was followed by unexpected characters.
+    #[error("unexpected trailing characters")]
+    TrailingCharacters,
+}
+
 /// The error type for connecting to KDB.
 #[derive(Debug, Error)]
 pub enum ConnectionError {
@@ -1,8 +1,10 @@
 use crate::date_time_types::*;
-use crate::k_type::KTypeCode;
+use crate::k_type::*;
 use crate::symbol::Symbol;
-use std::ffi::c_void;
+use std::convert::TryInto;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::{ptr, slice};
 
 pub type S = *const i8;
 pub type C = i8;
@@ -101,11 +103,15 @@ pub struct K {
 pub struct Attr(u8);
 
 impl Attr {
+    pub const NONE: Attr = Attr(0);
+    pub const SORTED: Attr = Attr(1);
+    pub const UNIQUE: Attr = Attr(2);
+
     pub fn sorted(self) -> bool {
-        self.0 == 1
+        self.0 == Attr::SORTED.0
     }
     pub fn unique(self) -> bool {
-        self.0 == 2
+        self.0 == Attr::UNIQUE.0
     }
     pub fn partioned(self) -> bool {
         self.0 == 3
@@ -148,26 +154,230 @@ impl fmt::Debug for K {
     }
 }
 
-extern "C" {
-    fn memcmp(cx: *const c_void, ct: *const c_void, n: usize) -> i32;
+impl K {
+    /// The attributes of a list.
+    #[inline]
+    pub(crate) fn attr(&self) -> Attr {
+        Attr(self.u as u8)
+    }
+
+    #[inline]
+    pub(crate) fn set_attr(&mut self, attr: Attr) {
+        self.u = attr.0 as C;
+    }
+
+    /// The items of a simple list as raw bytes.
+    pub(crate) unsafe fn list_bytes(&self) -> &[u8] {
+        let len = self.union.list.n as usize * self.t.list_item_size();
+        slice::from_raw_parts(self.union.list.g0.as_ptr(), len)
+    }
+
+    /// The items of a mixed list.
+    pub(crate) unsafe fn list_items(&self) -> &[&K] {
+        slice::from_raw_parts(self.union.list.g0.as_ptr() as *const &K, self.union.list.n as usize)
+    }
+
+    /// The raw bytes of an atom.
+    pub(crate) unsafe fn atom_bytes(&self) -> &[u8] {
+        if self.t == GUID_ATOM {
+            slice::from_raw_parts(self.union.list.g0.as_ptr(), 16)
+        } else {
+            slice::from_raw_parts(&self.union as *const _ as *const u8, self.t.atom_size())
+        }
+    }
+
+    /// The items of a real, float or datetime atom or list, widened to `f64`.
+    unsafe fn floats(&self) -> impl Iterator<Item = f64> + '_ {
+        let bytes = if i32::from(self.t) < 0 {
+            self.atom_bytes()
+        } else {
+            self.list_bytes()
+        };
+        let real = self.t.as_list() == REAL_LIST;
+        let size = if real { 4 } else { 8 };
+        bytes.chunks_exact(size).map(move |c| float_from_bytes(real, c))
+    }
+
+    /// The raw bytes of item `i` of a simple list.
+    pub(crate) unsafe fn item_bytes(&self, i: usize) -> &[u8] {
+        let size = self.t.list_item_size();
+        &self.list_bytes()[i * size..(i + 1) * size]
+    }
+
+    /// Returns true if item `i` of a simple list matches an atom, with the same result as copying the item into an
+    /// atom and comparing the two, but without allocating.
+    pub(crate) unsafe fn item_matches(&self, i: usize, atom: &K) -> bool {
+        if atom.t != self.t.as_atom() {
+            false
+        } else if is_float(self.t) {
+            let real = self.t == REAL_LIST;
+            float_bits(float_from_bytes(real, self.item_bytes(i)))
+                == float_bits(float_from_bytes(real, atom.atom_bytes()))
+        } else {
+            self.item_bytes(i) == atom.atom_bytes()
+        }
+    }
+
+    /// Hashes item `i` of a simple list in the same way as the item would be hashed once copied into an atom.
+    pub(crate) unsafe fn hash_item<H: Hasher>(&self, i: usize, state: &mut H) {
+        self.t.as_atom().hash(state);
+        if is_float(self.t) {
+            float_bits(float_from_bytes(self.t == REAL_LIST, self.item_bytes(i))).hash(state);
+        } else {
+            self.item_bytes(i).hash(state);
+        }
+    }
+}
+
+fn float_from_bytes(real: bool, bytes: &[u8]) -> f64 {
+    if real {
+        f64::from(f32::from_ne_bytes(bytes.try_into().unwrap()))
+    } else {
+        f64::from_ne_bytes(bytes.try_into().unwrap())
+    }
 }
 
+/// Maps a float to bits that are equal for values that match in q, so all NaNs are the same and `0.0` matches `-0.0`.
+fn float_bits(f: f64) -> u64 {
+    if f.is_nan() {
+        f64::NAN.to_bits()
+    } else if f == 0.0 {
+        0
+    } else {
+        f.to_bits()
+    }
+}
+
+fn is_float(t: KTypeCode) -> bool {
+    matches!(t.as_list(), REAL_LIST | FLOAT_LIST | DATE_TIME_LIST)
+}
+
+/// Structural equality with the same semantics as q's match (`~`): the types must be the same, and lists,
+/// dictionaries and tables match if all their items match. Attributes are ignored, and all float nulls match each other.
+/// Floats are compared exactly, rather than with q's comparison tolerance.
 impl PartialEq for K {
     fn eq(&self, other: &K) -> bool {
         if self.t != other.t {
             return false;
         }
+        unsafe {
+            match self.t {
+                t if is_float(t) => {
+                    (i32::from(t) < 0 || self.union.list.n == other.union.list.n)
+                        && self.floats().map(float_bits).eq(other.floats().map(float_bits))
+                }
+                t if i32::from(t) < 0 && t != ERROR => self.atom_bytes() == other.atom_bytes(),
+                t if t.is_simple_list() => self.list_bytes() == other.list_bytes(),
+                MIXED_LIST => self.list_items() == other.list_items(),
+                DICT => *self.union.dict.k == *other.union.dict.k && *self.union.dict.v == *other.union.dict.v,
+                TABLE => *self.union.k0 == *other.union.k0,
+                ERROR => self.union.s == other.union.s,
+                GENERIC_NULL => true,
+                _ => ptr::eq(self, other),
+            }
+        }
+    }
+}
+
+impl Eq for K {}
 
-        match i32::from(self.t) {
-            //Atoms
-            t if t > -20 && t < 0 => unsafe {
-                memcmp(
-                    &self.union as *const _ as _,
-                    &other.union as *const _ as _,
-                    self.t.atom_size(),
-                ) == 0
-            },
-            _ => unimplemented!("Comparison for non-atom K objects not implemented"),
+/// Hashes a K object consistently with its structural equality.
+impl Hash for K {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.t.hash(state);
+        unsafe {
+            match self.t {
+                t if is_float(t) => {
+                    for f in self.floats() {
+                        float_bits(f).hash(state);
+                    }
+                }
+                t if i32::from(t) < 0 && t != ERROR => self.atom_bytes().hash(state),
+                t if t.is_simple_list() => self.list_bytes().hash(state),
+                MIXED_LIST => self.list_items().hash(state),
+                DICT => {
+                    (*self.union.dict.k).hash(state);
+                    (*self.union.dict.v).hash(state);
+                }
+                TABLE => (*self.union.k0).hash(state),
+                ERROR => self.union.s.hash(state),
+                GENERIC_NULL => {}
+                _ => ptr::hash(self, state),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::list;
+    use crate::literal::literal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn values_match_structurally() {
+        assert_eq!(literal("1 2 3"), literal("1 2 3"));
+        assert_ne!(literal("1 2 3"), literal("1 2 3i"));
+        assert_ne!(literal("1 2 3"), literal("1 2"));
+        assert_eq!(literal("(1;`a;\"bc\")"), literal("(1;`a;\"bc\")"));
+        assert_ne!(literal("(1;`a;\"bc\")"), literal("(1;`a;\"bd\")"));
+        assert_eq!(literal("`a`b!(1 2;3f)"), literal("`a`b!(1 2;3f)"));
+        assert_ne!(literal("`a`b!1 2"), literal("`a`c!1 2"));
+        assert_eq!(literal("([] a:1 2; b:`x`y)"), literal("flip `a`b!(1 2;`x`y)"));
+        assert_eq!(literal("([k:1 2] v:`x`y)"), literal("([k:1 2] v:`x`y)"));
+        assert_ne!(literal("([k:1 2] v:`x`y)"), literal("([] k:1 2; v:`x`y)"));
+        assert_eq!(literal("::"), literal("::"));
+    }
+
+    #[test]
+    fn float_nulls_match_each_other() {
+        assert_eq!(literal("0n"), literal("0n"));
+        assert_eq!(literal("1 0n 2e"), literal("1 0n 2e"));
+        assert_eq!(literal("0f"), literal("-0f"));
+        assert_ne!(literal("1.5 2"), literal("1.5 2.5"));
+    }
+
+    #[test]
+    fn list_items_match_and_hash_like_atoms() {
+        use crate::list::simple_item_as_atom;
+        use crate::type_traits::KObject;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        for (list, atom) in [
+            ("1 0n -0f", "0f"),
+            ("1 0n -0f", "0n"),
+            ("1.5 0Ne", "0Ne"),
+            ("`a`b", "`b"),
+            ("3 4", "4"),
+        ] {
+            let (list, atom) = (literal(list), literal(atom));
+            let list = unsafe { &*list.k_ptr() };
+            for i in 0..unsafe { list.union.list.n } as usize {
+                let item = unsafe { simple_item_as_atom(list, i) };
+                let matches = unsafe { list.item_matches(i, &*atom.k_ptr()) };
+                assert_eq!(matches, item == atom);
+
+                let (mut a, mut b) = (DefaultHasher::new(), DefaultHasher::new());
+                unsafe { list.hash_item(i, &mut a) };
+                item.hash(&mut b);
+                assert_eq!(a.finish(), b.finish());
+            }
+        }
+    }
+
+    #[test]
+    fn boxes_can_be_used_as_hash_map_keys() {
+        let mut map = HashMap::new();
+        map.insert(literal("`a`b"), 1);
+        map.insert(literal("(1;2 3)"), 2);
+        map.insert(literal("-0f"), 3);
+        map.insert(list![i64; 1, 2].into(), 4);
+
+        assert_eq!(map.get(&literal("`a`b")), Some(&1));
+        assert_eq!(map.get(&literal("(1;2 3)")), Some(&2));
+        assert_eq!(map.get(&literal("0f")), Some(&3));
+        assert_eq!(map.get(&literal("1 2")), Some(&4));
+        assert_eq!(map.get(&literal("1 2i")), None);
+    }
+}
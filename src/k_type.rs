@@ -31,7 +31,7 @@ impl TypeCode {
 }
 
 #[repr(transparent)]
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Hash)]
 pub struct KTypeCode(i8);
 
 impl From<KTypeCode> for i32 {
@@ -83,6 +83,7 @@ impl fmt::Display for KTypeCode {
             TABLE => write!(f, "table"),
             DICT => write!(f, "dict"),
             ERROR => write!(f, "error"),
+            GENERIC_NULL => write!(f, "generic null"),
             _ => write!(f, "Unknown"),
         }
     }
@@ -97,9 +98,39 @@ impl KTypeCode {
             LONG_LIST | FLOAT_LIST | DATE_TIME_LIST | TIMESTAMP_LIST | TIMESPAN_LIST => 8,
             GUID_LIST => 24, // Guid has an 8 byte length as well as 16 bytes for the guid
             SYMBOL_LIST | MIXED_LIST | TABLE | DICT | ERROR => std::mem::size_of::<*const u8>(),
+            // enumerations store the index of each item in the domain as a long.
+            t if t.is_enum_list() => 8,
             _ => panic!("Unknown K type: {}", self.0),
         }
     }
+
+    /// The size of a single item when stored in a list of this type.
+    pub(crate) fn list_item_size(self) -> usize {
+        match KTypeCode(self.0.abs()) {
+            GUID_LIST => 16,
+            _ => self.atom_size(),
+        }
+    }
+
+    /// The atom type code that corresponds to a list or atom of this type.
+    pub(crate) fn as_atom(self) -> KTypeCode {
+        KTypeCode(-self.0.abs())
+    }
+
+    /// The list type code that corresponds to a list or atom of this type.
+    pub(crate) fn as_list(self) -> KTypeCode {
+        KTypeCode(self.0.abs())
+    }
+
+    /// Returns true if this is the type code of a simple (non-mixed) list.
+    pub(crate) fn is_simple_list(self) -> bool {
+        self.0 > 0 && self.0 < 20
+    }
+
+    /// Returns true if this is the type code of an enumerated list.
+    pub(crate) fn is_enum_list(self) -> bool {
+        (20..=76).contains(&self.0)
+    }
 }
 
 impl fmt::Debug for KTypeCode {
@@ -147,4 +178,5 @@ pub const SECOND_LIST: KTypeCode = KTypeCode(18);
 pub const TIME_LIST: KTypeCode = KTypeCode(19);
 pub const TABLE: KTypeCode = KTypeCode(98);
 pub const DICT: KTypeCode = KTypeCode(99);
+pub const GENERIC_NULL: KTypeCode = KTypeCode(101);
 pub const ERROR: KTypeCode = KTypeCode(-128);
@@ -1,5 +1,7 @@
 use crate::type_traits::KObject;
 use crate::{k::K, kapi};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 use std::mem::ManuallyDrop;
 use std::ops::Deref;
 use std::ptr::NonNull;
@@ -9,8 +11,10 @@ use std::{fmt, ops::DerefMut};
 /// KDB equivalent of a Rust Box, a zero overhead wrapper
 /// around a K pointer. It will call `r0` to decrement the reference
 /// count when it is dropped.
+///
+/// Comparing and hashing a `KBox` compares and hashes the value it points to, so a `KBox<Any>` can be used as the
+/// key of a `HashMap` or `BTreeMap`.
 #[repr(transparent)]
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct KBox<T: KObject> {
     pub(crate) k: NonNull<T>,
 }
@@ -45,7 +49,7 @@ impl<T: KObject> KBox<T> {
     /// about what happens to shared references (especially when reallocating a list for example)
     ///
     /// However in the embedded case, where you do not own the parameter and you wish to manipulate it
-    /// without copying the data, then you need this functionality.
+    /// without copying the data, then you need this functionality. To share an object you own, use `KRc` instead.
     ///
     /// # Safety
     ///
@@ -85,6 +89,32 @@ impl<T: KObject> AsRef<T> for KBox<T> {
     }
 }
 
+impl<T: KObject + PartialEq> PartialEq for KBox<T> {
+    fn eq(&self, other: &KBox<T>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: KObject + Eq> Eq for KBox<T> {}
+
+impl<T: KObject + PartialOrd> PartialOrd for KBox<T> {
+    fn partial_cmp(&self, other: &KBox<T>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: KObject + Ord> Ord for KBox<T> {
+    fn cmp(&self, other: &KBox<T>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: KObject + Hash> Hash for KBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
 impl<T: KObject + fmt::Debug> fmt::Debug for KBox<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "KBox({:?})", self.as_ref())
@@ -93,6 +123,6 @@ impl<T: KObject + fmt::Debug> fmt::Debug for KBox<T> {
 
 impl<T: KObject + fmt::Display> fmt::Display for KBox<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_ref())
+        fmt::Display::fmt(&**self, f)
     }
 }
@@ -0,0 +1,247 @@
+use crate::copy::copy_k;
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::type_traits::KObject;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+/// A reference counted K pointer, the KDB equivalent of a Rust `Rc`. Cloning a `KRc` calls `r1` to increment the
+/// reference count of the underlying K object, and dropping one calls `r0`.
+///
+/// Because the object may be shared, a `KRc` only gives read-only access to it. Use `KRc::make_mut` to get a
+/// mutable reference, which copies the object first if it is shared, or `KRc::try_unwrap` to turn a uniquely
+/// owned `KRc` back into a `KBox`. Objects that can't be copied, such as functions, can only be changed while
+/// they are uniquely owned.
+///
+/// # Example
+/// ```
+/// use kdb::{list, KRc};
+///
+/// let mut a = KRc::new(list![i64; 1, 2, 3]);
+/// let b = a.clone();
+/// assert_eq!(KRc::ref_count(&a), 2);
+///
+/// KRc::make_mut(&mut a).unwrap()[0] = 10;
+/// assert_eq!(a.as_slice(), &[10, 2, 3]);
+/// assert_eq!(b.as_slice(), &[1, 2, 3]);
+/// ```
+#[repr(transparent)]
+pub struct KRc<T: KObject> {
+    k: NonNull<T>,
+}
+
+impl<T: KObject> KRc<T> {
+    /// Takes ownership of a boxed K object, so that it can be shared.
+    pub fn new(value: KBox<T>) -> Self {
+        KRc {
+            k: ManuallyDrop::new(value).k,
+        }
+    }
+
+    /// The number of references to the underlying K object, including ones held by KDB itself.
+    pub fn ref_count(this: &Self) -> usize {
+        unsafe { (*this.k_ptr()).r as usize + 1 }
+    }
+
+    /// Returns true if both `KRc`s point to the same K object.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.k == other.k
+    }
+
+    /// Returns a mutable reference to the K object if this is the only reference to it.
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if KRc::ref_count(this) == 1 {
+            Some(unsafe { this.k.as_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the K object. If the object is shared it is copied first, and this `KRc`
+    /// is pointed at the copy, leaving the other references untouched. Returns `None` if the object is shared and
+    /// can't be copied because it is, or contains, a function.
+    pub fn make_mut(this: &mut Self) -> Option<&mut T> {
+        if KRc::ref_count(this) > 1 {
+            unsafe {
+                let copy = copy_k(&*this.k_ptr())?;
+                kapi::r0(this.k_ptr() as *mut _);
+                this.k = NonNull::new_unchecked(copy as *mut T);
+            }
+        }
+        Some(unsafe { this.k.as_mut() })
+    }
+
+    /// Converts the `KRc` into a `KBox` if this is the only reference to the K object, otherwise returns it
+    /// unchanged.
+    pub fn try_unwrap(this: Self) -> Result<KBox<T>, Self> {
+        if KRc::ref_count(&this) == 1 {
+            Ok(KBox {
+                k: ManuallyDrop::new(this).k,
+            })
+        } else {
+            Err(this)
+        }
+    }
+
+    /// Converts the `KRc` into a `KBox`, copying the K object if it is shared. If it is shared and can't be
+    /// copied, the `KRc` is returned unchanged.
+    pub fn unwrap_or_copy(mut this: Self) -> Result<KBox<T>, Self> {
+        if KRc::make_mut(&mut this).is_none() {
+            return Err(this);
+        }
+        KRc::try_unwrap(this)
+    }
+}
+
+impl<T: KObject> Clone for KRc<T> {
+    fn clone(&self) -> Self {
+        unsafe {
+            kapi::r1(self.k_ptr() as *mut _);
+        }
+        KRc { k: self.k }
+    }
+}
+
+impl<T: KObject> Drop for KRc<T> {
+    fn drop(&mut self) {
+        unsafe {
+            kapi::r0(self.k.as_mut().k_ptr_mut());
+        }
+    }
+}
+
+impl<T: KObject> Deref for KRc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.k.as_ref() }
+    }
+}
+
+impl<T: KObject> AsRef<T> for KRc<T> {
+    fn as_ref(&self) -> &T {
+        unsafe { self.k.as_ref() }
+    }
+}
+
+impl<T: KObject> From<KBox<T>> for KRc<T> {
+    fn from(value: KBox<T>) -> Self {
+        KRc::new(value)
+    }
+}
+
+impl<T: KObject + PartialEq> PartialEq for KRc<T> {
+    fn eq(&self, other: &KRc<T>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: KObject + Eq> Eq for KRc<T> {}
+
+impl<T: KObject + PartialOrd> PartialOrd for KRc<T> {
+    fn partial_cmp(&self, other: &KRc<T>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: KObject + Ord> Ord for KRc<T> {
+    fn cmp(&self, other: &KRc<T>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: KObject + Hash> Hash for KRc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: KObject + fmt::Debug> fmt::Debug for KRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KRc({:?})", self.as_ref())
+    }
+}
+
+impl<T: KObject + fmt::Display> fmt::Display for KRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k_type::KTypeCode;
+    use crate::{list, parse_literal, Any, Dictionary};
+
+    #[test]
+    fn clone_shares_the_object() {
+        let a = KRc::new(list![i64; 1, 2, 3]);
+        let b = a.clone();
+        assert!(KRc::ptr_eq(&a, &b));
+        assert_eq!(KRc::ref_count(&b), 2);
+        drop(a);
+        assert_eq!(KRc::ref_count(&b), 1);
+        assert_eq!(KRc::try_unwrap(b).ok().unwrap().as_slice(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn try_unwrap_fails_when_shared() {
+        let a = KRc::new(list![i64; 1]);
+        let b = a.clone();
+        let a = KRc::try_unwrap(a).err().unwrap();
+        assert!(KRc::ptr_eq(&a, &b));
+    }
+
+    #[test]
+    fn make_mut_copies_shared_objects() {
+        let mut a: KRc<Any> = KRc::new(parse_literal("`a`b!(1 2;`x)").unwrap());
+        let b = a.clone();
+        assert!(KRc::make_mut(&mut a).is_some());
+        assert!(!KRc::ptr_eq(&a, &b));
+        assert_eq!(*a, *b);
+        assert_eq!(KRc::ref_count(&a), 1);
+        assert_eq!(KRc::ref_count(&b), 1);
+
+        let values = unsafe { (*crate::cast!(&*a; Dictionary).k_ptr()).union.dict.v };
+        let original = unsafe { (*crate::cast!(&*b; Dictionary).k_ptr()).union.dict.v };
+        assert_ne!(values, original);
+    }
+
+    #[test]
+    fn make_mut_does_not_copy_unique_objects() {
+        let mut a = KRc::new(list![i64; 1, 2]);
+        let before = a.k;
+        KRc::make_mut(&mut a).unwrap()[0] = 3;
+        assert_eq!(a.as_slice(), &[3, 2]);
+        assert!(KRc::get_mut(&mut a).is_some());
+        assert_eq!(before, a.k);
+    }
+
+    #[test]
+    fn unwrap_or_copy_leaves_other_references_alone() {
+        let a = KRc::new(list![i64; 1, 2]);
+        let b = a.clone();
+        let mut boxed = KRc::unwrap_or_copy(a).ok().unwrap();
+        boxed.push(3);
+        assert_eq!(b.as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    fn shared_functions_are_not_copied() {
+        let mut a: KRc<Any> = KRc::new(KBox::new_atom(1i64).into());
+        // pretend the atom is a lambda.
+        unsafe { *(&mut (*a.k.as_mut().k_ptr_mut()).t as *mut KTypeCode as *mut i8) = 100 };
+        let b = a.clone();
+        assert!(KRc::make_mut(&mut a).is_none());
+        assert!(KRc::ptr_eq(&a, &b));
+        let a = KRc::unwrap_or_copy(a).err().unwrap();
+        drop(b);
+        let mut a = KRc::unwrap_or_copy(a).ok().unwrap();
+        unsafe { *(&mut (*a.k_ptr_mut()).t as *mut KTypeCode as *mut i8) = -7 };
+    }
+}
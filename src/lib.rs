@@ -11,13 +11,13 @@
 //! # Why you might like it
 //!
 //! 1. It's really fast. The abstractions around the KDB types have zero overhead
-//! so while it feels like you are using rust types with all the trimmings, it is as fast as calling
-//! the raw C API directly.
+//!    so while it feels like you are using rust types with all the trimmings, it is as fast as calling
+//!    the raw C API directly.
 //! 2. It's safe! Using the C API is full of pitfalls that cause undefined behaviour.
-//! The abstractions in rust-kdb manage these for you, so that you don't need to worry about
-//! freeing your KDB objects or weird behaviour.
+//!    The abstractions in rust-kdb manage these for you, so that you don't need to worry about
+//!    freeing your KDB objects or weird behaviour.
 //! 3. It's just like using other rust data types. We've worked hard to make Lists work like
-//! Rust vectors, and to add similar convenience functions to dictionaries.
+//!    Rust vectors, and to add similar convenience functions to dictionaries.
 //!
 //! # Why you might not like it
 //!
@@ -96,15 +96,31 @@
 //!    Using a KBox here will likely cause a segfault.
 //! 2. The return type is always either a KBox<T> or Option<KBox<T>>. This is equivalent to returning a K pointer. Always return an owned type.
 //! 3. You can use typed atoms for parameters, not just Any. Bear in mind that this is unsafe as it is possible for q code to call the function
-//! with a type other than that one. Any is always safest.
+//!    with a type other than that one. Any is always safest.
 
 #![warn(missing_docs)] // warn if there are missing docs
 
+#[cfg(test)]
+extern crate self as kdb;
+
 mod any;
+mod any_view;
+#[cfg(feature = "arrow")]
+mod arrow;
 mod atom;
+mod byte_list;
 mod callbacks;
+#[cfg(feature = "chrono")]
+mod chrono_support;
+mod compare;
 mod connection;
+mod console;
+mod conversion;
+mod copy;
 mod date_time_types;
+#[cfg(feature = "serde")]
+mod de;
+mod dict_index;
 mod dictionary;
 mod error;
 mod k;
@@ -112,26 +128,46 @@ mod k_error;
 mod k_type;
 pub mod kapi;
 mod kbox;
+mod krc;
 mod list;
+mod literal;
+mod parser;
+mod q_string;
+#[cfg(feature = "serde")]
+mod ser;
 mod serialization;
+mod string;
 mod symbol;
 mod table;
+#[cfg(feature = "time")]
+mod time_support;
 mod type_traits;
+mod visit;
 
 pub use any::{Any, KdbCast};
+pub use any_view::{AnyBox, AnyRef};
 pub use array_iterator;
 pub use atom::Atom;
 pub use callbacks::*;
+pub use compare::compare;
 pub use connection::Connection;
+#[doc(hidden)]
+pub use conversion::__private;
+pub use conversion::{FromKdb, IntoKdb};
 pub use date_time_types::*;
-pub use dictionary::Dictionary;
-pub use error::{ConnectionError, ConversionError, Error};
+pub use dict_index::IndexedDictionary;
+pub use dictionary::{Dict, Dictionary, Entry, OccupiedEntry, VacantEntry};
+pub use error::{ConnectionError, ConversionError, Error, ParseError, ParseErrorKind};
 pub use k_error::KError;
 pub use kbox::KBox;
+pub use krc::KRc;
 pub use list::List;
+pub use literal::parse_literal;
+pub use q_string::to_q_string;
 pub use serialization::*;
 pub use symbol::{symbol, Symbol};
 pub use table::Table;
+pub use visit::{walk, walk_mut, KVisitor, KVisitorMut};
 
 /// [not-embedded] Initialize the kdb memory subsystem. this is required when using generator functions
 /// in a standalone kdb application. This is equivalent to calling `khp("", -1)` in the C api.
@@ -145,3 +181,19 @@ pub fn init() {
 
 #[cfg(feature = "uuid")]
 pub use uuid;
+
+#[cfg(feature = "chrono")]
+pub use chrono;
+
+#[cfg(feature = "time")]
+pub use time;
+
+#[cfg(feature = "derive")]
+pub use kdb_derive::{FromKdb, IntoKdb};
+
+#[cfg(feature = "arrow")]
+pub use arrow::{from_arrow_array, to_arrow_array, KDB_TYPE_KEY};
+#[cfg(feature = "serde")]
+pub use de::{from_any, Deserializer};
+#[cfg(feature = "serde")]
+pub use ser::{to_any, Serializer};
@@ -1,11 +1,14 @@
+use crate::k::{Attr, K};
 use crate::kbox::KBox;
 use crate::type_traits::*;
-use crate::{k::K, ConversionError};
-use crate::{k_type::KTypeCode, kapi};
-use std::slice;
+use crate::{any::Any, ConversionError};
+use crate::{k_type::*, kapi};
+use std::hash::{Hash, Hasher};
+use std::ops::{Bound, RangeBounds};
 use std::{marker::PhantomData, slice::SliceIndex};
-use std::{mem, str};
+use std::{mem, str, vec};
 use std::{ops, ptr::NonNull};
+use std::{ptr, slice};
 
 use std::{
     iter::FromIterator,
@@ -17,7 +20,7 @@ unsafe fn as_slice_uninit<'a, T>(k: *mut K) -> &'a mut [mem::MaybeUninit<T>] {
     slice::from_raw_parts_mut(&list.g0 as *const _ as *mut _, list.n as usize)
 }
 
-unsafe fn as_slice_mut<'a, T>(k: *mut K) -> &'a mut [T] {
+pub(crate) unsafe fn as_slice_mut<'a, T>(k: *mut K) -> &'a mut [T] {
     let list = &(*k).union.list;
     slice::from_raw_parts_mut(&list.g0 as *const _ as *mut _, list.n as usize)
 }
@@ -27,6 +30,172 @@ unsafe fn as_slice<'a, T>(k: *const K) -> &'a [T] {
     slice::from_raw_parts(&list.g0 as *const _ as *const _, list.n as usize)
 }
 
+/// Copies the item at `index` out of a simple list into a newly allocated atom of the same type.
+pub(crate) unsafe fn simple_item_as_atom(k: *const K, index: usize) -> KBox<Any> {
+    let t = (*k).t;
+    let size = t.list_item_size();
+    let src = (&(*k).union.list.g0 as *const u8).add(index * size);
+    let atom = if t == GUID_LIST {
+        // guid atoms are stored as a single item list.
+        let atom = kapi::ktn(GUID_LIST.into(), 1);
+        (*atom).t = GUID_ATOM;
+        ptr::copy_nonoverlapping(src, &mut (*atom).union.list.g0 as *mut u8, size);
+        atom
+    } else {
+        let atom = kapi::ka(t.as_atom().into());
+        ptr::copy_nonoverlapping(src, &mut (*atom).union as *mut _ as *mut u8, size);
+        atom
+    };
+    KBox::from_raw(atom)
+}
+
+/// Writes the null value for items of the list type `t` to `dest`.
+/// Returns false if the type has no null value (booleans and bytes).
+unsafe fn write_null(t: KTypeCode, dest: *mut u8) -> bool {
+    match t {
+        SHORT_LIST => *(dest as *mut i16) = i16::MIN,
+        INT_LIST | MONTH_LIST | DATE_LIST | MINUTE_LIST | SECOND_LIST | TIME_LIST => *(dest as *mut i32) = i32::MIN,
+        LONG_LIST | TIMESTAMP_LIST | TIMESPAN_LIST => *(dest as *mut i64) = i64::MIN,
+        REAL_LIST => *(dest as *mut f32) = f32::NAN,
+        FLOAT_LIST | DATE_TIME_LIST => *(dest as *mut f64) = f64::NAN,
+        CHAR_LIST => *dest = b' ',
+        SYMBOL_LIST => *(dest as *mut *const i8) = kapi::ss(b"\0".as_ptr() as *const i8),
+        GUID_LIST => ptr::write_bytes(dest, 0, 16),
+        _ => return false,
+    }
+    true
+}
+
+/// Creates a simple list from a set of atoms that all share the same type, the same way q collapses
+/// a general list of atoms. Any generic nulls `(::)` are replaced with the null value for that type.
+///
+/// Returns `None` if there are no atoms, if the items are not all atoms of the same type, or if
+/// the items contain generic nulls but the type has no null value.
+pub(crate) fn simple_list_from_atoms(items: &[KBox<Any>]) -> Option<KBox<Any>> {
+    let t = items.iter().map(|i| i.k_type()).find(|t| *t != GENERIC_NULL)?;
+    if i32::from(t) >= 0
+        || t == ERROR
+        || !t.as_list().is_simple_list()
+        || items.iter().any(|i| i.k_type() != t && i.k_type() != GENERIC_NULL)
+    {
+        return None;
+    }
+    let list_type = t.as_list();
+    let size = list_type.list_item_size();
+    unsafe {
+        let list = KBox::<Any>::from_raw(kapi::ktn(list_type.into(), items.len() as i64));
+        let data = &(*list.k_ptr()).union.list.g0 as *const u8 as *mut u8;
+        for (i, item) in items.iter().enumerate() {
+            let dest = data.add(i * size);
+            if item.k_type() == GENERIC_NULL {
+                if !write_null(list_type, dest) {
+                    return None;
+                }
+            } else {
+                let u = &(*item.k_ptr()).union;
+                let src = if t == GUID_ATOM {
+                    &u.list.g0 as *const u8
+                } else {
+                    u as *const _ as *const u8
+                };
+                ptr::copy_nonoverlapping(src, dest, size);
+            }
+        }
+        Some(list)
+    }
+}
+
+/// Returns an owned reference to the item at `index` in a simple or mixed list.
+/// Items of simple lists are copied into new atoms, items of mixed lists have their reference count incremented.
+pub(crate) unsafe fn list_item(k: *const K, index: usize) -> KBox<Any> {
+    if (*k).t == MIXED_LIST {
+        let item = *(&(*k).union.list.g0 as *const u8 as *const *mut K).add(index);
+        KBox::from_raw(kapi::r1(item))
+    } else {
+        simple_item_as_atom(k, index)
+    }
+}
+
+/// Converts a simple list into a mixed list of atoms.
+pub(crate) unsafe fn make_mixed(list: &mut *mut K) {
+    let n = (**list).union.list.n;
+    let mixed = kapi::ktn(MIXED_LIST.into(), n);
+    let items = (*mixed).union.list.g0.as_mut_ptr() as *mut *mut K;
+    for i in 0..n as usize {
+        *items.add(i) = simple_item_as_atom(*list, i).into_raw() as *mut K;
+    }
+    kapi::r0(*list);
+    *list = mixed;
+}
+
+/// Returns true if `item` can be stored in the list without converting it to a mixed list.
+unsafe fn fits(list: *const K, item: &Any) -> bool {
+    (*list).t == MIXED_LIST || item.k_type() == (*list).t.as_atom()
+}
+
+/// Appends any value to a simple or mixed list. If the list is a simple list and the value is not an atom of the
+/// same type, the list is converted into a mixed list first, in the same way as q's join.
+pub(crate) unsafe fn push_item(list: &mut *mut K, item: KBox<Any>) {
+    if !fits(*list, &item) {
+        make_mixed(list);
+    }
+    (**list).set_attr(Attr::NONE);
+    *list = if (**list).t == MIXED_LIST {
+        kapi::jk(list, item.into_raw() as *const K)
+    } else {
+        kapi::ja(list, (*item.k_ptr()).atom_bytes().as_ptr() as *const _)
+    };
+}
+
+/// Replaces the item at `index` in a simple or mixed list, returning the old item. The list is converted into a
+/// mixed list if the new item doesn't match its type.
+pub(crate) unsafe fn replace_item(list: &mut *mut K, index: usize, item: KBox<Any>) -> KBox<Any> {
+    if !fits(*list, &item) {
+        make_mixed(list);
+    }
+    let k = &mut **list;
+    k.set_attr(Attr::NONE);
+    if k.t == MIXED_LIST {
+        let slot = (k.union.list.g0.as_mut_ptr() as *mut *mut K).add(index);
+        let old = KBox::from_raw(*slot);
+        *slot = item.into_raw() as *mut K;
+        old
+    } else {
+        let old = simple_item_as_atom(k, index);
+        let size = k.t.list_item_size();
+        let dest = k.union.list.g0.as_mut_ptr().add(index * size);
+        ptr::copy_nonoverlapping((*item.k_ptr()).atom_bytes().as_ptr(), dest, size);
+        old
+    }
+}
+
+/// Keeps only the items of a simple or mixed list for which `keep` returns true, preserving their order.
+/// Items removed from a mixed list are released.
+pub(crate) unsafe fn retain_items(list: *mut K, mut keep: impl FnMut(usize) -> bool) {
+    let k = &mut *list;
+    let size = k.t.list_item_size();
+    let data = k.union.list.g0.as_mut_ptr();
+    let mut kept = 0;
+    for i in 0..k.union.list.n as usize {
+        if keep(i) {
+            if kept != i {
+                ptr::copy_nonoverlapping(data.add(i * size), data.add(kept * size), size);
+            }
+            kept += 1;
+        } else if k.t == MIXED_LIST {
+            kapi::r0(*(data.add(i * size) as *mut *mut K));
+        }
+    }
+    k.union.list.n = kept as i64;
+}
+
+/// Removes the item at `index` from a simple or mixed list, returning it.
+pub(crate) unsafe fn remove_item(list: *mut K, index: usize) -> KBox<Any> {
+    let item = list_item(list, index);
+    retain_items(list, |i| i != index);
+    item
+}
+
 /// Lists are the KDB equivalent of Rust's `Vec`. They contain collections of values
 /// and their contents be looked up by index.
 ///
@@ -63,15 +232,17 @@ impl<T: KListable> List<T> {
         unsafe { as_slice(&self.k) }
     }
 
-    /// Returns the contents of the list as a mutable slice
+    /// Returns the contents of the list as a mutable slice. This removes any attributes from the list,
+    /// as they may no longer hold once it has been changed.
     #[inline]
     pub fn as_slice_mut(&mut self) -> &mut [T::ListItem] {
+        self.k.set_attr(Attr::NONE);
         unsafe { as_slice_mut(&mut self.k) }
     }
 
     /// Returns an iterator over the list.
     #[inline]
-    pub fn iter(&self) -> Iter<T::ListItem> {
+    pub fn iter(&self) -> Iter<'_, T::ListItem> {
         self.as_slice().iter()
     }
 
@@ -89,7 +260,7 @@ impl<T: KListable> List<T> {
 
     /// Returns an iterator that allows modifying each value.
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<T::ListItem> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T::ListItem> {
         self.as_slice_mut().iter_mut()
     }
 
@@ -106,6 +277,28 @@ impl<T: KListable> List<T> {
     }
 }
 
+/// Resolves a range of a list of length `len` into its start and end.
+fn bounds(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n + 1,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end && end <= len,
+        "range {}..{} out of bounds for length {}",
+        start,
+        end,
+        len
+    );
+    (start, end)
+}
+
 impl<T: KListable> KBox<List<T>> {
     /// Creates a new empty list.
     ///
@@ -116,27 +309,392 @@ impl<T: KListable> KBox<List<T>> {
         unsafe { mem::transmute(kapi::ktn(T::LIST_TYPE_CODE.into(), 0)) }
     }
 
+    /// Creates a new empty list with room for at least `capacity` items, so that pushing up to that many items
+    /// doesn't reallocate it.
+    pub fn with_capacity(capacity: usize) -> Self {
+        unsafe {
+            let k = kapi::ktn(T::LIST_TYPE_CODE.into(), capacity as i64);
+            (*k).union.list.n = 0;
+            mem::transmute(k)
+        }
+    }
+
+    /// Reallocates the list with room for exactly `additional` more items. KDB doesn't report the capacity of a
+    /// list, so this always reallocates and copies the items unless `additional` is zero. Reserve room for all
+    /// the items up front, rather than calling this before each `push`.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if additional == 0 {
+            return;
+        }
+        unsafe { self.reallocate(self.len() + additional) };
+    }
+
+    /// Moves the items into a newly allocated list with room for `capacity` items.
+    unsafe fn reallocate(&mut self, capacity: usize) {
+        let len = self.len();
+        let k = kapi::ktn(T::LIST_TYPE_CODE.into(), capacity as i64);
+        let dest = (*k).union.list.g0.as_mut_ptr() as *mut T::ListItem;
+        ptr::copy_nonoverlapping(self.as_slice().as_ptr(), dest, len);
+        (*k).union.list.n = len as i64;
+        (*k).u = (**self).k.u;
+        // the items have been moved to the new list, so mustn't be released with the old one.
+        self.set_len(0);
+        kapi::r0(self.k.as_ptr() as *mut K);
+        self.k = NonNull::new_unchecked(k as *mut List<T>);
+    }
+
+    /// Appends copies of all the items in a slice. The list is reallocated once with `ktn`, and the existing items
+    /// and the new ones are each copied with a single memcpy, rather than being pushed one at a time.
+    pub fn extend_from_slice(&mut self, items: &[T::ListItem])
+    where
+        T::ListItem: Copy,
+    {
+        if items.is_empty() {
+            return;
+        }
+        let len = self.len();
+        unsafe {
+            self.reallocate(len + items.len());
+            let dest = ((**self).k.union.list.g0.as_mut_ptr() as *mut T::ListItem).add(len);
+            ptr::copy_nonoverlapping(items.as_ptr(), dest, items.len());
+            self.set_len(len + items.len());
+        }
+        (**self).k.set_attr(Attr::NONE);
+    }
+
     /// Appends a list to this one, consuming it and adding it's elements to the new one.
     #[inline]
     pub fn join(&mut self, list: KBox<List<T>>) {
+        (**self).k.set_attr(Attr::NONE);
+        // jv copies the items (incrementing the reference counts of mixed list items), so `list` is still
+        // dropped afterwards.
         unsafe {
-            self.k = NonNull::new_unchecked(
-                kapi::jv(&mut (self.k.as_ptr() as *mut K), list.into_raw() as *const K) as *mut K as *mut List<T>
-            )
+            self.k = NonNull::new_unchecked(kapi::jv(&mut (self.k.as_ptr() as *mut K), list.k_ptr()) as *mut List<T>)
         }
     }
 
     /// Appends an element to the end of the list.
     #[inline]
     pub fn push(&mut self, item: T::ListItem) {
+        (**self).k.set_attr(Attr::NONE);
         unsafe {
             self.k = NonNull::new_unchecked(T::join_to(item, self.k.as_ptr() as *mut K) as *mut List<T>);
         }
     }
+
+    /// Sets the length of the list without dropping or initialising any items.
+    unsafe fn set_len(&mut self, len: usize) {
+        (**self).k.union.list.n = len as i64;
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T::ListItem {
+        self.as_slice_mut().as_mut_ptr()
+    }
+
+    /// Removes the last item from the list and returns it, or `None` if it is empty.
+    pub fn pop(&mut self) -> Option<T::ListItem> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        unsafe {
+            let item = ptr::read(self.as_mut_ptr().add(len - 1));
+            self.set_len(len - 1);
+            Some(item)
+        }
+    }
+
+    /// Inserts an item at position `index`, shifting all the items after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, item: T::ListItem) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        self.push(item);
+        self.as_slice_mut()[index..].rotate_right(1);
+    }
+
+    /// Removes and returns the item at position `index`, shifting all the items after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T::ListItem {
+        let len = self.len();
+        assert!(index < len, "removal index (is {}) should be < len (is {})", index, len);
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            let item = ptr::read(p);
+            ptr::copy(p.add(1), p, len - index - 1);
+            self.set_len(len - 1);
+            item
+        }
+    }
+
+    /// Removes and returns the item at position `index`, replacing it with the last item of the list.
+    /// This doesn't preserve the order of the list, but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T::ListItem {
+        let len = self.len();
+        assert!(
+            index < len,
+            "swap_remove index (is {}) should be < len (is {})",
+            index,
+            len
+        );
+        self.as_slice_mut().swap(index, len - 1);
+        self.pop().unwrap()
+    }
+
+    /// Shortens the list to `len` items, dropping the rest. Does nothing if the list is already shorter.
+    pub fn truncate(&mut self, len: usize) {
+        let old_len = self.len();
+        if len >= old_len {
+            return;
+        }
+        unsafe {
+            let tail = ptr::slice_from_raw_parts_mut(self.as_mut_ptr().add(len), old_len - len);
+            self.set_len(len);
+            ptr::drop_in_place(tail);
+        }
+    }
+
+    /// Removes all the items from the list.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Keeps only the items for which `f` returns true, preserving their order.
+    pub fn retain(&mut self, mut f: impl FnMut(&T::ListItem) -> bool) {
+        let len = self.len();
+        let data = self.as_mut_ptr();
+        let mut kept = 0;
+        unsafe {
+            // if `f` panics the remaining items are leaked rather than dropped twice.
+            self.set_len(0);
+            for i in 0..len {
+                let item = data.add(i);
+                if f(&*item) {
+                    if kept != i {
+                        ptr::copy_nonoverlapping(item, data.add(kept), 1);
+                    }
+                    kept += 1;
+                } else {
+                    ptr::drop_in_place(item);
+                }
+            }
+            self.set_len(kept);
+        }
+    }
+
+    /// Removes consecutive items that `same` considers equal, keeping the first of each run.
+    /// `same` is passed the item being considered and the last item kept.
+    pub fn dedup_by(&mut self, mut same: impl FnMut(&T::ListItem, &T::ListItem) -> bool) {
+        let len = self.len();
+        if len < 2 {
+            return;
+        }
+        let data = self.as_mut_ptr();
+        let mut kept = 1;
+        unsafe {
+            self.set_len(0);
+            for i in 1..len {
+                let item = data.add(i);
+                if same(&*item, &*data.add(kept - 1)) {
+                    ptr::drop_in_place(item);
+                } else {
+                    if kept != i {
+                        ptr::copy_nonoverlapping(item, data.add(kept), 1);
+                    }
+                    kept += 1;
+                }
+            }
+            self.set_len(kept);
+        }
+    }
+
+    /// Removes consecutive repeated items, like q's `differ` followed by a where.
+    pub fn dedup(&mut self)
+    where
+        T::ListItem: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Removes the items in `range` from the list, returning them in an iterator.
+    /// Unlike `Vec::drain`, the items are removed straight away, even if the iterator isn't used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> vec::IntoIter<T::ListItem> {
+        let len = self.len();
+        let (start, end) = bounds(range, len);
+        let mut drained = Vec::with_capacity(end - start);
+        unsafe {
+            let data = self.as_mut_ptr();
+            ptr::copy_nonoverlapping(data.add(start), drained.as_mut_ptr(), end - start);
+            drained.set_len(end - start);
+            ptr::copy(data.add(end), data.add(start), len - end);
+            self.set_len(len - (end - start));
+        }
+        drained.into_iter()
+    }
+
+    /// Appends copies of the items in `range` to the end of the list. Items of a `List<Any>` are copied with
+    /// `KBox::clone`, so they share nothing with the originals except functions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn extend_from_within(&mut self, range: impl RangeBounds<usize>)
+    where
+        T::ListItem: Clone,
+    {
+        let (start, end) = bounds(range, self.len());
+        for i in start..end {
+            let item = self[i].clone();
+            self.push(item);
+        }
+    }
+
+    /// Splits the list in two at `at`, returning a new list with the items from `at` onwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> KBox<List<T>> {
+        let len = self.len();
+        assert!(at <= len, "`at` split index (is {}) should be <= len (is {})", at, len);
+        unsafe {
+            let tail = kapi::ktn(T::LIST_TYPE_CODE.into(), (len - at) as i64);
+            let dest = (*tail).union.list.g0.as_mut_ptr() as *mut T::ListItem;
+            ptr::copy_nonoverlapping(self.as_mut_ptr().add(at), dest, len - at);
+            self.set_len(at);
+            KBox::from_raw(tail)
+        }
+    }
+
+    /// Converts the list into a general (mixed) list, with each item of a simple list copied into an atom. Lists
+    /// that are already general lists are returned unchanged.
+    pub fn into_general(self) -> KBox<List<Any>> {
+        unsafe {
+            let mut k = self.into_raw() as *mut K;
+            if (*k).t != MIXED_LIST {
+                make_mixed(&mut k);
+            }
+            KBox::from_raw(k)
+        }
+    }
+}
+
+impl KBox<List<Any>> {
+    /// Collapses a general list of atoms that all have the same type into a simple list of that type, in the same
+    /// way q does. Returns the list unchanged if it is empty, or if its items are not all atoms of the same type.
+    ///
+    /// # Example
+    /// ```
+    /// use kdb::{cast, list, Any, List};
+    ///
+    /// let l = list![Any; 1i64, 2i64];
+    /// let l = l.try_into_simple().ok().unwrap();
+    /// assert_eq!(cast!(l; List<i64>).as_slice(), &[1, 2]);
+    /// ```
+    pub fn try_into_simple(self) -> Result<KBox<Any>, KBox<List<Any>>> {
+        match self.as_slice().first() {
+            Some(first) if self.iter().all(|item| item.k_type() == first.k_type()) => {}
+            _ => return Err(self),
+        }
+        simple_list_from_atoms(self.as_slice()).ok_or(self)
+    }
+}
+
+/// Collapses any general lists of same-typed atoms nested inside a value into simple lists, including the keys and
+/// values of dictionaries and the columns of tables. Objects with more than one reference are left alone, as
+/// changing them would change them for the other references too.
+pub(crate) fn normalize(any: KBox<Any>) -> KBox<Any> {
+    unsafe {
+        let k = any.k_ptr() as *mut K;
+        if (*k).r > 0 {
+            return any;
+        }
+        match (*k).t {
+            MIXED_LIST => {
+                let items = (*k).union.list.g0.as_mut_ptr() as *mut *mut K;
+                for i in 0..(*k).union.list.n as usize {
+                    normalize_in_place(&mut *items.add(i));
+                }
+                match mem::transmute::<KBox<Any>, KBox<List<Any>>>(any).try_into_simple() {
+                    Ok(simple) => simple,
+                    Err(list) => list.into(),
+                }
+            }
+            DICT => {
+                normalize_in_place(&mut (*k).union.dict.k);
+                normalize_in_place(&mut (*k).union.dict.v);
+                any
+            }
+            TABLE => {
+                normalize_in_place(&mut (*k).union.k0);
+                any
+            }
+            _ => any,
+        }
+    }
+}
+
+unsafe fn normalize_in_place(k: &mut *mut K) {
+    *k = normalize(KBox::from_raw(*k)).into_raw() as *mut K;
 }
 
 impl<T: KListable> KTyped for List<T> {
     const K_TYPE: KTypeCode = T::LIST_TYPE_CODE;
+
+    fn check_type(k: &K) -> Result<(), ConversionError> {
+        if k.t != Self::K_TYPE {
+            return Err(ConversionError::InvalidKCast {
+                from: k.t,
+                to: Self::K_TYPE,
+            });
+        }
+        T::check_items(k)
+    }
+}
+
+/// Checks that every item of a mixed list can be viewed as a `T`.
+pub(crate) fn check_mixed_items<T: KTyped>(list: &K) -> Result<(), ConversionError> {
+    for (index, item) in unsafe { list.list_items() }.iter().enumerate() {
+        T::check_type(item).map_err(|e| ConversionError::InvalidItem {
+            index,
+            source: Box::new(e),
+        })?;
+    }
+    Ok(())
+}
+
+/// A list of lists is stored as a mixed list, such as a string column (`List<List<i8>>`). Casting to it checks
+/// that every item is a list of the right type, so the items can be used without casting them one by one.
+impl<T: KListable> KListable for List<T> {
+    const LIST_TYPE_CODE: KTypeCode = MIXED_LIST;
+    type ListItem = KBox<List<T>>;
+
+    unsafe fn join_to(item: Self::ListItem, mut k: *mut K) -> *mut K {
+        kapi::jk(&mut k, item.into_raw() as *const K)
+    }
+
+    fn check_items(list: &K) -> Result<(), ConversionError> {
+        check_mixed_items::<List<T>>(list)
+    }
 }
 
 impl List<i8> {
@@ -174,6 +732,20 @@ impl<T> KObject for List<T> {
 
 impl<T> private::Sealed for List<T> {}
 
+impl<T> PartialEq for List<T> {
+    fn eq(&self, other: &List<T>) -> bool {
+        self.k == other.k
+    }
+}
+
+impl<T> Eq for List<T> {}
+
+impl<T> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.k.hash(state)
+    }
+}
+
 impl<T: KListable> ops::Index<ops::RangeFrom<usize>> for List<T> {
     type Output = [T::ListItem];
     fn index(&self, i: ops::RangeFrom<usize>) -> &Self::Output {
@@ -252,10 +824,10 @@ impl<T: KListable> FromIterator<T::ListItem> for KBox<List<T>> {
                     .iter_mut()
                     .zip(iter)
                     .for_each(|(dest, src)| *dest = mem::MaybeUninit::new(src));
-                unsafe { mem::transmute(k) }
+                unsafe { mem::transmute::<*mut K, Self>(k) }
             }
-            _ => {
-                let mut list = Self::new_list();
+            (x, _) => {
+                let mut list = Self::with_capacity(x);
                 list.extend(iter);
                 list
             }
@@ -271,6 +843,35 @@ impl<T: KListable> Extend<T::ListItem> for KBox<List<T>> {
     }
 }
 
+/// Copies a slice into a new list, allocating it once and copying the items with a single memcpy.
+impl<T: KListable> From<&[T::ListItem]> for KBox<List<T>>
+where
+    T::ListItem: Copy,
+{
+    fn from(items: &[T::ListItem]) -> Self {
+        unsafe {
+            let k = kapi::ktn(T::LIST_TYPE_CODE.into(), items.len() as i64);
+            let dest = (*k).union.list.g0.as_mut_ptr() as *mut T::ListItem;
+            ptr::copy_nonoverlapping(items.as_ptr(), dest, items.len());
+            KBox::from_raw(k)
+        }
+    }
+}
+
+/// Moves the items of a `Vec` into a new list, allocating it once and copying the items with a single memcpy.
+impl<T: KListable> From<Vec<T::ListItem>> for KBox<List<T>> {
+    fn from(mut items: Vec<T::ListItem>) -> Self {
+        unsafe {
+            let k = kapi::ktn(T::LIST_TYPE_CODE.into(), items.len() as i64);
+            let dest = (*k).union.list.g0.as_mut_ptr() as *mut T::ListItem;
+            ptr::copy_nonoverlapping(items.as_ptr(), dest, items.len());
+            // the items now belong to the list.
+            items.set_len(0);
+            KBox::from_raw(k)
+        }
+    }
+}
+
 /// Create a list from a set of supplied values.
 ///
 /// #Example
@@ -377,4 +978,152 @@ mod test {
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn vec_like_edits_work_on_simple_lists() {
+        let mut l = list![i64; 1, 2, 3, 4, 5];
+        l.insert(0, 0);
+        l.insert(6, 6);
+        assert_eq!(l.remove(1), 1);
+        assert_eq!(l.swap_remove(0), 0);
+        assert_eq!(l.as_slice(), &[6, 2, 3, 4, 5]);
+        assert_eq!(l.drain(1..3).collect::<Vec<_>>(), vec![2, 3]);
+        let tail = l.split_off(1);
+        assert_eq!(tail.as_slice(), &[4, 5]);
+        l.extend(7..10);
+        l.extend_from_within(..2);
+        assert_eq!(l.as_slice(), &[6, 7, 8, 9, 6, 7]);
+        l.retain(|x| x % 2 == 1);
+        assert_eq!(l.as_slice(), &[7, 9, 7]);
+        l.truncate(1);
+        assert_eq!(l.pop(), Some(7));
+        assert!(l.is_empty());
+
+        let mut l = list![Symbol; symbol("a"), symbol("a"), symbol("b"), symbol("a")];
+        l.dedup();
+        assert_eq!(l.as_slice(), &[symbol("a"), symbol("b"), symbol("a")]);
+        l.clear();
+        assert!(l.is_empty());
+    }
+
+    #[test]
+    fn removed_items_of_mixed_lists_are_released() {
+        use crate::type_traits::KObject;
+        use crate::{kapi, Any, KBox};
+
+        let item = KBox::<Any>::from(symbol("x"));
+        let raw = item.k_ptr() as *mut crate::k::K;
+        unsafe { kapi::r1(raw) };
+        let mut l = list![Any; 1, item, 2];
+        assert_eq!(unsafe { (*raw).r }, 1);
+        l.retain(|i| i.k_type() == crate::k_type::SYMBOL_ATOM);
+        l.truncate(0);
+        assert_eq!(unsafe { (*raw).r }, 0);
+        unsafe { kapi::r0(raw) };
+
+        let mut l = list![Any; 1, symbol("a"), 2];
+        let removed = l.remove(1);
+        assert_eq!(removed, KBox::from(symbol("a")));
+        l.insert(0, removed);
+        let other = l.split_off(2);
+        assert_eq!(l.as_slice(), &[KBox::from(symbol("a")), KBox::from(1)]);
+        assert_eq!(other.as_slice(), &[KBox::from(2)]);
+        l.join(other);
+        assert_eq!(l.len(), 3);
+    }
+
+    #[test]
+    fn lists_can_be_preallocated_and_copied_from_slices() {
+        use crate::type_traits::KObject;
+        use crate::{KBox, List};
+
+        let mut l = KBox::<List<i64>>::with_capacity(10);
+        assert!(l.is_empty());
+        l.extend(0..3);
+        l.reserve_exact(100);
+        l.extend_from_slice(&[3, 4]);
+        assert_eq!(l.as_slice(), &[0, 1, 2, 3, 4]);
+        let before = l.k_ptr();
+        l.extend_from_slice(&[]);
+        assert_eq!(l.k_ptr(), before);
+
+        let mut l = list![i64; 1, 2, 3];
+        l.sort();
+        l.extend_from_slice(&[0]);
+        assert_eq!(l.as_slice(), &[1, 2, 3, 0]);
+        assert!(!unsafe { (*l.k_ptr()).attr().sorted() });
+
+        let l = KBox::<List<Symbol>>::from(&[symbol("a"), symbol("b")][..]);
+        assert_eq!(l.as_slice(), &[symbol("a"), symbol("b")]);
+
+        let l = KBox::<List<crate::Any>>::from(vec![KBox::from(1), KBox::from(symbol("a"))]);
+        assert_eq!(l.as_slice(), &[KBox::from(1), KBox::from(symbol("a"))]);
+    }
+
+    #[test]
+    fn nested_lists_are_typed_views_over_mixed_lists() {
+        use crate::{cast, parse_literal, try_cast, ConversionError, Dictionary, KBox, List, Table};
+
+        let strings = cast!(parse_literal("(\"ab\";\"cd\")").unwrap(); List<List<i8>>);
+        let words: Vec<&str> = strings.iter().map(|s| s.try_as_str().unwrap()).collect();
+        assert_eq!(words, vec!["ab", "cd"]);
+
+        let mixed = parse_literal("(\"ab\";`c)").unwrap();
+        match try_cast!(&mixed; List<List<i8>>) {
+            Err(ConversionError::InvalidItem { index: 1, .. }) => {}
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        let mut l = KBox::<List<List<i64>>>::new_list();
+        l.push(list![i64; 1, 2]);
+        l.push(list![i64; 3]);
+        assert_eq!(l[1].as_slice(), &[3]);
+        assert_eq!(crate::to_q_string(l.as_ref()), "(1 2;,3)");
+
+        let dicts = parse_literal("(`a`b!1 2;`c`d!3 4)").unwrap();
+        assert_eq!(cast!(&dicts; List<Dictionary>)[1].len(), 2);
+        let tables = parse_literal("(([] a:1 2);([] b:`x`y`z))").unwrap();
+        assert_eq!(cast!(&tables; List<Table>)[1].len(), 3);
+        assert!(try_cast!(&tables; List<Dictionary>).is_err());
+    }
+
+    #[test]
+    fn general_lists_collapse_to_simple_lists() {
+        use crate::{parse_literal, Any, KBox, List};
+        let l = list![Any; 1i64, 2i64];
+        assert_eq!(l.try_into_simple().ok().unwrap(), parse_literal("1 2").unwrap());
+        assert!(list![Any; 1i64, symbol("a")].try_into_simple().is_err());
+        assert!(list![Any; 1i64, parse_literal("::").unwrap()]
+            .try_into_simple()
+            .is_err());
+        assert!(KBox::<List<Any>>::new_list().try_into_simple().is_err());
+
+        let general = list![i32; 1, 2].into_general();
+        assert_eq!(general[1], KBox::<Any>::from(2i32));
+        assert_eq!(general.try_into_simple().ok().unwrap(), parse_literal("1 2i").unwrap());
+    }
+
+    #[test]
+    fn normalize_collapses_nested_lists() {
+        use super::normalize;
+        use crate::{cast, Any, Dictionary, KBox, List};
+        let nested = list![Any; 1i64, list![Any; symbol("a"), symbol("b")]];
+        let mut dict = KBox::<Dictionary>::new_dict();
+        dict.insert(symbol("x"), nested);
+        let dict = cast!(normalize(dict.into()); Dictionary);
+        assert_eq!(cast!(dict.keys_any(); List<Symbol>).as_slice(), &[symbol("x")]);
+        let nested = cast!(&cast!(dict.values_any(); List<Any>)[0]; List<Any>);
+        assert_eq!(cast!(&nested[1]; List<Symbol>).as_slice(), &[symbol("a"), symbol("b")]);
+    }
+
+    #[test]
+    fn pushed_symbols_read_back() {
+        use crate::KBox;
+
+        let mut l = KBox::<crate::List<Symbol>>::new_list();
+        l.push(symbol("hello"));
+        l.push(symbol("world"));
+        assert_eq!(l.as_slice(), &[symbol("hello"), symbol("world")]);
+        assert_eq!(l[1].try_as_str().unwrap(), "world");
+    }
 }
@@ -0,0 +1,638 @@
+//! A parser for q data literals, such as `` `a`b!(1 2;"hello") ``.
+use crate::any::{generic_null, Any};
+use crate::date_time_types::*;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::k::K;
+use crate::k_type::*;
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::list::{simple_list_from_atoms, List};
+use crate::parser::Cursor;
+use crate::symbol::Symbol;
+use crate::table::Table;
+use crate::try_cast;
+use crate::type_traits::{KListable, KObject, KValue};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// Parses a q data literal into a K object, without needing a q process.
+///
+/// Supported syntax:
+/// * numeric atoms and vectors, with an optional type suffix: `42`, `1 2 3h`, `1.5 2 0n`, `0N 1i`, `0W`.
+/// * booleans and bytes: `1b`, `0110b`, `0x2a`, `0x0102`.
+/// * temporal values: `2020.01.01`, `2020.01m`, `2020.01.01D10:15:00.000000000`, `0D00:00:01`, `10:15`,
+///   `10:15:30`, `10:15:30.123`, `2020.01.01T10:15:30.123` and typed nulls such as `0Np`.
+/// * chars, strings and symbols: `"c"`, `"hello"`, `` `a ``, `` `a`b`c ``.
+/// * general lists `(1;`a;"b")`, the empty list `()` and the generic null `::`.
+/// * dictionaries `` `a`b!1 2 ``, tables `` flip `a`b!(1 2;3 4) `` and `([] a:1 2; b:`x`y)`, and
+///   keyed tables `([k:1 2] v:3 4)`.
+/// * `enlist x`.
+///
+/// # Example
+/// ```
+/// use kdb::{cast, parse_literal, Dictionary};
+///
+/// let k = parse_literal("`a`b!(1 2;3 4 5f)").unwrap();
+/// let dict = cast!(k; Dictionary);
+/// let b = kdb::__private::get_field::<Vec<f64>>(&dict, "b").unwrap();
+/// assert_eq!(b, vec![3.0, 4.0, 5.0]);
+/// ```
+pub fn parse_literal(s: &str) -> Result<KBox<Any>, ParseError> {
+    let mut c = Cursor::new(s);
+    let value = expr(&mut c)?;
+    skip_spaces(&mut c);
+    c.finish()?;
+    Ok(value)
+}
+
+/// Parses a literal that is known to be valid, for building test values.
+#[cfg(test)]
+pub(crate) fn literal(s: &str) -> KBox<Any> {
+    parse_literal(s).unwrap()
+}
+
+fn skip_spaces(c: &mut Cursor) {
+    c.take_while(|b| b == b' ' || b == b'\t' || b == b'\n' || b == b'\r');
+}
+
+fn is_name_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Consumes `word` if it is the next (whole) word in the input.
+fn keyword(c: &mut Cursor, word: &str) -> bool {
+    let rest = c.rest();
+    if rest.starts_with(word)
+        && !rest
+            .as_bytes()
+            .get(word.len())
+            .copied()
+            .map(is_name_char)
+            .unwrap_or(false)
+    {
+        c.reset(c.pos() + word.len());
+        true
+    } else {
+        false
+    }
+}
+
+fn expr(c: &mut Cursor) -> Result<KBox<Any>, ParseError> {
+    skip_spaces(c);
+    let start = c.pos();
+    if keyword(c, "flip") {
+        let value = expr(c)?;
+        return flip(value, start);
+    }
+    if keyword(c, "enlist") {
+        return Ok(enlist(expr(c)?));
+    }
+    let left = term(c)?;
+    skip_spaces(c);
+    let bang = c.pos();
+    if c.eat(b'!') {
+        let right = expr(c)?;
+        return dict(left, right, bang);
+    }
+    Ok(left)
+}
+
+fn term(c: &mut Cursor) -> Result<KBox<Any>, ParseError> {
+    match c.peek() {
+        Some(b'(') => {
+            c.eat(b'(');
+            skip_spaces(c);
+            if c.peek() == Some(b'[') {
+                table(c)
+            } else {
+                general_list(c)
+            }
+        }
+        Some(b'`') => Ok(symbols(c)),
+        Some(b'"') => string(c),
+        Some(b':') if c.peek_at(1) == Some(b':') => {
+            c.reset(c.pos() + 2);
+            Ok(generic_null())
+        }
+        Some(b'0'..=b'9') | Some(b'-') => numbers(c),
+        _ => Err(c.error(ParseErrorKind::Expected("value"))),
+    }
+}
+
+fn general_list(c: &mut Cursor) -> Result<KBox<Any>, ParseError> {
+    let mut items = Vec::new();
+    skip_spaces(c);
+    if !c.eat(b')') {
+        loop {
+            items.push(expr(c)?);
+            skip_spaces(c);
+            if !c.eat(b';') {
+                break;
+            }
+        }
+        c.expect(b')', "')'")?;
+    }
+    if items.len() == 1 {
+        return Ok(items.pop().unwrap());
+    }
+    Ok(collapse(items))
+}
+
+/// Creates a list from a set of items, using a simple list if they are all atoms of the same type.
+fn collapse(items: Vec<KBox<Any>>) -> KBox<Any> {
+    let first = items.first().map(|i| i.k_type());
+    if first.map(|t| i32::from(t) < 0 && t != ERROR).unwrap_or(false) && items.iter().all(|i| Some(i.k_type()) == first)
+    {
+        if let Some(list) = simple_list_from_atoms(&items) {
+            return list;
+        }
+    }
+    let mut list = KBox::<List<Any>>::new_list();
+    for item in items {
+        list.push(item);
+    }
+    list.into()
+}
+
+fn enlist(value: KBox<Any>) -> KBox<Any> {
+    collapse(vec![value])
+}
+
+fn dict(keys: KBox<Any>, values: KBox<Any>, pos: usize) -> Result<KBox<Any>, ParseError> {
+    match (len(&keys), len(&values)) {
+        (Some(k), Some(v)) if k == v => {}
+        _ => return Err(Cursor::error_at(pos, ParseErrorKind::Length)),
+    }
+    unsafe {
+        let dict = kapi::xD(keys.into_raw() as *const K, values.into_raw() as *const K);
+        Ok(KBox::from_raw(dict as *mut K))
+    }
+}
+
+fn len(k: &Any) -> Option<usize> {
+    if k.k_type() == TABLE {
+        Some(try_cast!(k; Table).unwrap().len())
+    } else {
+        k.list_len()
+    }
+}
+
+fn flip(value: KBox<Any>, pos: usize) -> Result<KBox<Any>, ParseError> {
+    let error = || Cursor::error_at(pos, ParseErrorKind::Expected("dictionary of symbols to columns"));
+    if value.k_type() != DICT {
+        return Err(error());
+    }
+    let (keys, values) = unsafe {
+        let d = (*value.k_ptr()).union.dict;
+        (&*(d.k as *const Any), &*(d.v as *const Any))
+    };
+    let names = try_cast!(keys; List<Symbol>).map_err(|_| error())?;
+    let columns = try_cast!(values; List<Any>).map_err(|_| error())?;
+    new_table(
+        names.iter().copied().collect(),
+        columns
+            .iter()
+            .map(|c| unsafe { KBox::from_raw(kapi::r1(c.k_ptr() as *mut K)) })
+            .collect(),
+        pos,
+    )
+}
+
+fn new_table(names: KBox<List<Symbol>>, columns: Vec<KBox<Any>>, pos: usize) -> Result<KBox<Any>, ParseError> {
+    let mut list = KBox::<List<Any>>::new_list();
+    for column in columns {
+        list.push(column);
+    }
+    KBox::new_table(names, list)
+        .map(Into::into)
+        .map_err(|_| Cursor::error_at(pos, ParseErrorKind::Length))
+}
+
+/// The names and values of a set of table columns.
+type Columns = (KBox<List<Symbol>>, Vec<KBox<Any>>);
+
+/// Parses the columns of a table, up to the closing bracket `end`.
+fn columns(c: &mut Cursor, end: u8, what: &'static str) -> Result<Columns, ParseError> {
+    let mut names = KBox::<List<Symbol>>::new_list();
+    let mut columns = Vec::new();
+    skip_spaces(c);
+    if c.peek() != Some(end) {
+        loop {
+            skip_spaces(c);
+            let name = c.take_while(is_name_char);
+            if name.is_empty() {
+                return Err(c.error(ParseErrorKind::Expected("column name")));
+            }
+            names.push(Symbol::new(name).unwrap());
+            c.expect(b':', "':'")?;
+            columns.push(expr(c)?);
+            skip_spaces(c);
+            if !c.eat(b';') {
+                break;
+            }
+        }
+    }
+    c.expect(end, what)?;
+    Ok((names, columns))
+}
+
+fn table(c: &mut Cursor) -> Result<KBox<Any>, ParseError> {
+    let start = c.pos();
+    c.expect(b'[', "'['")?;
+    let (key_names, keys) = columns(c, b']', "']'")?;
+    let (names, values) = columns(c, b')', "')'")?;
+    let values = new_table(names, values, start)?;
+    if keys.is_empty() {
+        Ok(values)
+    } else {
+        dict(new_table(key_names, keys, start)?, values, start)
+    }
+}
+
+fn symbols(c: &mut Cursor) -> KBox<Any> {
+    let mut symbols = Vec::new();
+    while c.eat(b'`') {
+        let name = c.take_while(|b| is_name_char(b) || b == b'.' || b == b':' || b == b'/');
+        symbols.push(Symbol::new(name).unwrap());
+    }
+    build(symbols, false)
+}
+
+fn string(c: &mut Cursor) -> Result<KBox<Any>, ParseError> {
+    c.expect(b'"', "'\"'")?;
+    let mut bytes = Vec::new();
+    loop {
+        match c.peek() {
+            None => return Err(c.error(ParseErrorKind::Expected("'\"'"))),
+            Some(b'"') => {
+                c.eat(b'"');
+                break;
+            }
+            Some(b'\\') => {
+                c.eat(b'\\');
+                let escaped = match c.peek() {
+                    Some(b'n') => b'\n',
+                    Some(b't') => b'\t',
+                    Some(b'r') => b'\r',
+                    Some(b'"') => b'"',
+                    Some(b'\\') => b'\\',
+                    Some(b'0'..=b'7') => {
+                        let start = c.pos();
+                        let octal = c.take_while(|b| (b'0'..=b'7').contains(&b));
+                        let octal = &octal[..octal.len().min(3)];
+                        c.reset(start + octal.len());
+                        let value = u32::from_str_radix(octal, 8).unwrap();
+                        bytes.push(
+                            u8::try_from(value).map_err(|_| Cursor::error_at(start, ParseErrorKind::OutOfRange))?,
+                        );
+                        continue;
+                    }
+                    _ => return Err(c.error(ParseErrorKind::Expected("escape sequence"))),
+                };
+                c.reset(c.pos() + 1);
+                bytes.push(escaped);
+            }
+            Some(b) => {
+                c.reset(c.pos() + 1);
+                bytes.push(b);
+            }
+        }
+    }
+    Ok(if bytes.len() == 1 {
+        unsafe { KBox::from_raw(kapi::kc(bytes[0].into())) }
+    } else {
+        unsafe { KBox::from_raw(kapi::kpn(bytes.as_ptr() as *const i8, bytes.len() as i64)) }
+    })
+}
+
+fn is_number_start(c: &Cursor, offset: usize) -> bool {
+    match c.peek_at(offset) {
+        Some(b'0'..=b'9') => true,
+        Some(b'-') => matches!(c.peek_at(offset + 1), Some(b'0'..=b'9')),
+        _ => false,
+    }
+}
+
+/// Parses a numeric or temporal atom, or a vector of them separated by spaces.
+fn numbers(c: &mut Cursor) -> Result<KBox<Any>, ParseError> {
+    let mut tokens = Vec::new();
+    loop {
+        let start = c.pos();
+        let token = c.take_while(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b':' || b == b'-');
+        tokens.push((start, token));
+        let spaces = c.rest().bytes().take_while(|b| *b == b' ').count();
+        if spaces == 0 || !is_number_start(c, spaces) {
+            break;
+        }
+        c.reset(c.pos() + spaces);
+    }
+    if let [(start, token)] = tokens[..] {
+        if let Some(bits) = token.strip_suffix('b') {
+            return booleans(bits, start);
+        }
+        if let Some(hex) = token.strip_prefix("0x") {
+            return bytes(hex, start + 2);
+        }
+    }
+
+    let (last_start, last) = *tokens.last().unwrap();
+    let suffix = match last.as_bytes() {
+        [.., b] if b.is_ascii_lowercase() && !matches!(last, "0n" | "0w" | "-0w") => Some(*b),
+        _ => None,
+    };
+    let type_char = match suffix {
+        Some(suffix) => {
+            let n = tokens.len();
+            tokens[n - 1].1 = &last[..last.len() - 1];
+            if last.len() == 1 {
+                return Err(Cursor::error_at(last_start, ParseErrorKind::Expected("number")));
+            }
+            suffix
+        }
+        None => infer_type(&tokens)?,
+    };
+    let suffix_pos = last_start + last.len() - 1;
+    let atom = tokens.len() == 1;
+    match type_char {
+        b'h' => typed(&tokens, atom, |s| integer(s, i16::MAX.into()).map(|v| v as i16)),
+        b'i' => typed(&tokens, atom, |s| integer(s, i32::MAX.into()).map(|v| v as i32)),
+        b'j' => typed(&tokens, atom, |s| integer(s, i64::MAX)),
+        b'e' => typed(&tokens, atom, |s| float(s).map(|v| v as f32)),
+        b'f' => typed(&tokens, atom, float),
+        b'p' => typed(&tokens, atom, |s| temporal::<Timestamp>(s, 'p')),
+        b'm' => typed(&tokens, atom, |s| temporal::<Month>(s, 'm')),
+        b'd' => typed(&tokens, atom, |s| temporal::<Date>(s, 'd')),
+        b'z' => typed(&tokens, atom, |s| temporal::<DateTime>(s, 'z')),
+        b'n' => typed(&tokens, atom, |s| temporal::<Timespan>(s, 'n')),
+        b'u' => typed(&tokens, atom, |s| temporal::<Minute>(s, 'u')),
+        b'v' => typed(&tokens, atom, |s| temporal::<Second>(s, 'v')),
+        b't' => typed(&tokens, atom, |s| temporal::<Time>(s, 't')),
+        #[cfg(feature = "uuid")]
+        b'g' => typed(&tokens, atom, |s| match s {
+            "0N" => Ok(uuid::Uuid::nil()),
+            _ => Err(Cursor::error_at(0, ParseErrorKind::Expected("0N"))),
+        }),
+        _ => Err(Cursor::error_at(suffix_pos, ParseErrorKind::Expected("type suffix"))),
+    }
+}
+
+/// Works out the type of a vector without a type suffix from the form of its items.
+fn infer_type(tokens: &[(usize, &str)]) -> Result<u8, ParseError> {
+    let mut result = None;
+    for (start, token) in tokens {
+        let t = match *token {
+            "0N" | "0W" | "-0W" => continue,
+            "0n" | "0w" | "-0w" => b'f',
+            t if t.contains('D') => match (t.find('.'), t.find('D')) {
+                (Some(dot), Some(d)) if dot < d => b'p',
+                _ => b'n',
+            },
+            t if t.contains('T') => b'z',
+            t if t.contains(':') => match (t.matches(':').count(), t.contains('.')) {
+                (1, _) => b'u',
+                (_, false) => b'v',
+                (_, true) => b't',
+            },
+            t if t.matches('.').count() == 2 => b'd',
+            t if t.contains(['.', 'e', 'E'].as_ref()) => b'f',
+            _ => b'j',
+        };
+        result = match (result, t) {
+            (None, t) => Some(t),
+            (Some(a), b) if a == b => Some(a),
+            (Some(b'j'), b'f') | (Some(b'f'), b'j') => Some(b'f'),
+            _ => {
+                return Err(Cursor::error_at(
+                    *start,
+                    ParseErrorKind::Expected("item of the same type"),
+                ))
+            }
+        };
+    }
+    Ok(result.unwrap_or(b'j'))
+}
+
+fn typed<T: KValue + KListable<ListItem = T>>(
+    tokens: &[(usize, &str)],
+    atom: bool,
+    parse: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<KBox<Any>, ParseError> {
+    let values = tokens
+        .iter()
+        .map(|(start, token)| {
+            parse(token).map_err(|e| ParseError {
+                position: start + e.position,
+                kind: e.kind,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(build(values, atom))
+}
+
+fn build<T: KValue + KListable<ListItem = T>>(mut values: Vec<T>, atom: bool) -> KBox<Any> {
+    if atom || values.len() == 1 {
+        KBox::new_atom(values.pop().unwrap()).into()
+    } else {
+        values.into_iter().collect::<KBox<List<T>>>().into()
+    }
+}
+
+fn integer(s: &str, max: i64) -> Result<i64, ParseError> {
+    let value = match s {
+        "0N" => return Ok(-max - 1),
+        "0W" => return Ok(max),
+        "-0W" => return Ok(-max),
+        s => s
+            .parse::<i64>()
+            .map_err(|_| Cursor::error_at(0, ParseErrorKind::Expected("integer")))?,
+    };
+    if (-max..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(Cursor::error_at(0, ParseErrorKind::OutOfRange))
+    }
+}
+
+fn float(s: &str) -> Result<f64, ParseError> {
+    match s {
+        "0n" | "0N" => Ok(f64::NAN),
+        "0w" | "0W" => Ok(f64::INFINITY),
+        "-0w" | "-0W" => Ok(f64::NEG_INFINITY),
+        s => s
+            .parse::<f64>()
+            .map_err(|_| Cursor::error_at(0, ParseErrorKind::Expected("number"))),
+    }
+}
+
+fn temporal<T: FromStr<Err = ParseError>>(s: &str, type_char: char) -> Result<T, ParseError> {
+    if matches!(s, "0N" | "0W" | "-0W") || type_char == 'm' {
+        format!("{}{}", s, type_char).parse()
+    } else {
+        s.parse()
+    }
+}
+
+fn booleans(bits: &str, start: usize) -> Result<KBox<Any>, ParseError> {
+    let values = bits
+        .bytes()
+        .enumerate()
+        .map(|(i, b)| match b {
+            b'0' => Ok(false),
+            b'1' => Ok(true),
+            _ => Err(Cursor::error_at(start + i, ParseErrorKind::Expected("0 or 1"))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(build(values, bits.len() == 1))
+}
+
+fn bytes(hex: &str, start: usize) -> Result<KBox<Any>, ParseError> {
+    let values = hex
+        .as_bytes()
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let digits = std::str::from_utf8(pair).ok().filter(|d| d.len() == 2);
+            digits
+                .and_then(|d| u8::from_str_radix(d, 16).ok())
+                .ok_or_else(|| Cursor::error_at(start + i * 2, ParseErrorKind::Expected("hex digit")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.is_empty() {
+        return Ok(KBox::<List<u8>>::new_list().into());
+    }
+    Ok(build(values, hex.len() == 2))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+
+    use super::*;
+    use crate::__private::get_field;
+    use crate::{cast, symbol, Atom};
+
+    #[test]
+    fn parses_numeric_atoms_and_vectors() {
+        assert_eq!(cast!(parse_literal("42").unwrap(); Atom<i64>).value(), 42);
+        assert_eq!(cast!(parse_literal("-7i").unwrap(); Atom<i32>).value(), -7);
+        assert_eq!(
+            cast!(parse_literal("1 2 3h").unwrap(); List<i16>).as_slice(),
+            &[1, 2, 3]
+        );
+        assert_eq!(
+            cast!(parse_literal("1 2.5").unwrap(); List<f64>).as_slice(),
+            &[1.0, 2.5]
+        );
+        assert_eq!(
+            cast!(parse_literal("1 0N 0W").unwrap(); List<i64>).as_slice(),
+            &[1, i64::MIN, i64::MAX]
+        );
+        assert!(cast!(parse_literal("0n").unwrap(); Atom<f64>).value().is_nan());
+        assert_eq!(cast!(parse_literal("1.5e").unwrap(); Atom<f32>).value(), 1.5);
+        assert_eq!(
+            cast!(parse_literal("0110b").unwrap(); List<bool>).as_slice(),
+            &[false, true, true, false]
+        );
+        assert_eq!(cast!(parse_literal("0x2a").unwrap(); Atom<u8>).value(), 42);
+        assert_eq!(cast!(parse_literal("0x0102").unwrap(); List<u8>).as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    fn parses_temporal_values() {
+        assert_eq!(
+            cast!(parse_literal("2020.01.01").unwrap(); Atom<Date>).value(),
+            Date::new(2020, 1, 1)
+        );
+        assert_eq!(
+            cast!(parse_literal("2000.01 2000.03m").unwrap(); List<Month>).as_slice(),
+            &[Month::from(0), Month::from(2)]
+        );
+        assert_eq!(
+            cast!(parse_literal("2000.01.01D00:00:01 0Np").unwrap(); List<Timestamp>).as_slice(),
+            &[Timestamp::from_raw(1_000_000_000), Timestamp::from_raw(i64::MIN)]
+        );
+        assert_eq!(
+            cast!(parse_literal("0D00:00:01").unwrap(); Atom<Timespan>).value(),
+            Timespan::from_secs(1)
+        );
+        assert_eq!(
+            cast!(parse_literal("10:15").unwrap(); Atom<Minute>).value(),
+            Minute::from(615)
+        );
+        assert_eq!(
+            cast!(parse_literal("10:15:30").unwrap(); Atom<Second>).value(),
+            Second::from(36_930)
+        );
+        assert_eq!(
+            cast!(parse_literal("00:00:00.001").unwrap(); Atom<Time>).value(),
+            Time::from(1)
+        );
+    }
+
+    #[test]
+    fn parses_strings_and_symbols() {
+        assert_eq!(
+            cast!(parse_literal("\"a\\\"b\"").unwrap(); List<i8>)
+                .try_as_str()
+                .unwrap(),
+            "a\"b"
+        );
+        assert_eq!(cast!(parse_literal("\"c\"").unwrap(); Atom<i8>).value(), b'c' as i8);
+        assert_eq!(cast!(parse_literal("`a").unwrap(); Atom<Symbol>).value(), symbol("a"));
+        assert_eq!(
+            cast!(parse_literal("`a`b.c`").unwrap(); List<Symbol>).as_slice(),
+            &[symbol("a"), symbol("b.c"), symbol("")]
+        );
+    }
+
+    #[test]
+    fn parses_general_lists() {
+        let l = parse_literal("(1;`a;\"bc\";(2 3;::))").unwrap();
+        let l = cast!(l; List<Any>);
+        assert_eq!(l.len(), 4);
+        assert_eq!(l[3].k_type(), MIXED_LIST);
+        assert_eq!(
+            cast!(parse_literal("(1;2;3)").unwrap(); List<i64>).as_slice(),
+            &[1, 2, 3]
+        );
+        assert_eq!(parse_literal("()").unwrap().k_type(), MIXED_LIST);
+        assert_eq!(cast!(parse_literal("enlist 1").unwrap(); List<i64>).as_slice(), &[1]);
+    }
+
+    #[test]
+    fn parses_dictionaries_and_tables() {
+        let d = parse_literal("`a`b!(1 2;3 4 5f)").unwrap();
+        let d = cast!(d; crate::Dictionary);
+        assert_eq!(get_field::<Vec<i64>>(&d, "a").unwrap(), vec![1, 2]);
+
+        let t = parse_literal("flip `a`b!(1 2;`x`y)").unwrap();
+        assert_eq!(cast!(t; Table).len(), 2);
+
+        let t = parse_literal("([] a:1 2 3; b:\"xyz\")").unwrap();
+        let t = cast!(t; Table);
+        assert_eq!(t.column_names().as_slice(), &[symbol("a"), symbol("b")]);
+        assert_eq!(t.len(), 3);
+
+        let k = parse_literal("([k:`x`y] v:1 2)").unwrap();
+        assert_eq!(k.k_type(), DICT);
+    }
+
+    #[test]
+    fn reports_errors_with_positions() {
+        let err = parse_literal("`a`b!1 2 3").unwrap_err();
+        assert_eq!((err.position, err.kind), (4, ParseErrorKind::Length));
+        let err = parse_literal("(1;2").unwrap_err();
+        assert_eq!((err.position, err.kind), (4, ParseErrorKind::Expected("')'")));
+        let err = parse_literal("1 2020.01.01").unwrap_err();
+        assert_eq!(
+            (err.position, err.kind),
+            (2, ParseErrorKind::Expected("item of the same type"))
+        );
+        let err = parse_literal("2020.01.01 2020.13.01").unwrap_err();
+        assert_eq!((err.position, err.kind), (16, ParseErrorKind::OutOfRange));
+        let err = parse_literal("1 2 3q").unwrap_err();
+        assert_eq!((err.position, err.kind), (5, ParseErrorKind::Expected("type suffix")));
+        let err = parse_literal("32768h").unwrap_err();
+        assert_eq!((err.position, err.kind), (0, ParseErrorKind::OutOfRange));
+    }
+}
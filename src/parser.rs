@@ -0,0 +1,127 @@
+use crate::error::{ParseError, ParseErrorKind};
+
+/// A position in a string being parsed, tracked so that errors can report where they occurred.
+pub(crate) struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Cursor { input, pos: 0 }
+    }
+
+    #[inline]
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves back to a position previously returned by `pos`.
+    #[inline]
+    pub(crate) fn reset(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    #[inline]
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    /// Returns the character `offset` characters after the current position.
+    #[inline]
+    pub(crate) fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.input.as_bytes().get(self.pos + offset).copied()
+    }
+
+    /// The part of the input that hasn't been consumed yet.
+    #[inline]
+    pub(crate) fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    /// Consumes characters while `pred` returns true, returning them.
+    pub(crate) fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().map(&pred).unwrap_or(false) {
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    #[inline]
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    /// Consumes `b` if it is the next character.
+    pub(crate) fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn expect(&mut self, b: u8, what: &'static str) -> Result<(), ParseError> {
+        if self.eat(b) {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorKind::Expected(what)))
+        }
+    }
+
+    /// Consumes between `min` and `max` (at most 18) decimal digits, returning their value and the number of digits read.
+    pub(crate) fn digits(&mut self, min: usize, max: usize, what: &'static str) -> Result<(u64, usize), ParseError> {
+        let start = self.pos;
+        let mut value = 0u64;
+        while self.pos - start < max {
+            match self.peek() {
+                Some(b @ b'0'..=b'9') => {
+                    value = value * 10 + u64::from(b - b'0');
+                    self.pos += 1;
+                }
+                _ => break,
+            }
+        }
+        if self.pos - start < min {
+            return Err(self.error(ParseErrorKind::Expected(what)));
+        }
+        Ok((value, self.pos - start))
+    }
+
+    /// Consumes exactly `n` digits and checks that their value is in `range`. Errors are reported at the first digit.
+    pub(crate) fn number(
+        &mut self,
+        n: usize,
+        range: std::ops::RangeInclusive<u64>,
+        what: &'static str,
+    ) -> Result<u64, ParseError> {
+        let start = self.pos;
+        let (value, _) = self
+            .digits(n, n, what)
+            .map_err(|_| Self::error_at(start, ParseErrorKind::Expected(what)))?;
+        if range.contains(&value) {
+            Ok(value)
+        } else {
+            Err(Self::error_at(start, ParseErrorKind::OutOfRange))
+        }
+    }
+
+    pub(crate) fn error(&self, kind: ParseErrorKind) -> ParseError {
+        Self::error_at(self.pos, kind)
+    }
+
+    pub(crate) fn error_at(position: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError { position, kind }
+    }
+
+    /// Checks that the whole input has been consumed.
+    pub(crate) fn finish(&self) -> Result<(), ParseError> {
+        if self.is_at_end() {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorKind::TrailingCharacters))
+        }
+    }
+}
@@ -0,0 +1,520 @@
+//! Rendering K values as q source text, in the format produced by `-3!`.
+use crate::any::Any;
+use crate::date_time_types::*;
+use crate::k::K;
+use crate::k_type::*;
+use crate::type_traits::KObject;
+use std::ffi::CStr;
+use std::fmt::{self, Write};
+
+/// The number of significant digits used for floats, which is q's default `\P` setting.
+const FLOAT_PRECISION: i32 = 7;
+
+/// Renders a K value as q source text, in the same format as `-3!`.
+///
+/// Enumerations and functions are not supported, as the domain of an enumeration and the source of a function
+/// can't be read through the C API. They are written as their type code in angle brackets, such as `<20>`.
+///
+/// # Example
+/// ```
+/// use kdb::{list, symbol, to_q_string, KBox, Symbol};
+///
+/// let l = list![Symbol; symbol("a"), symbol("b")];
+/// assert_eq!(to_q_string(l.as_ref()), "`a`b");
+/// assert_eq!(to_q_string(KBox::new_atom(42i16).as_ref()), "42h");
+/// ```
+pub fn to_q_string(any: &Any) -> String {
+    let mut s = String::new();
+    // Writing to a string can't fail.
+    write_any(&mut s, any).unwrap();
+    s
+}
+
+impl Any {
+    /// Writes the value as q source text, in the same format as `-3!`.
+    ///
+    /// If `max_len` is set, output longer than `max_len` characters is cut short and ends with `..`, in the same
+    /// way as the q console does with the width set by `\c`.
+    pub fn fmt_q(&self, w: &mut dyn Write, max_len: Option<usize>) -> fmt::Result {
+        let max_len = match max_len {
+            Some(max_len) => max_len,
+            None => return write_any(w, self),
+        };
+        let mut limited = Limited {
+            out: String::new(),
+            limit: max_len + 1,
+        };
+        // This only fails once the limit has been reached.
+        let _ = write_any(&mut limited, self);
+        let mut s = limited.out;
+        if s.len() > max_len {
+            s.truncate(floor_char_boundary(&s, max_len.saturating_sub(2)));
+            s.push_str("..");
+        }
+        w.write_str(&s)
+    }
+}
+
+/// A writer that stops with an error once `limit` bytes have been written.
+struct Limited {
+    out: String,
+    limit: usize,
+}
+
+impl Write for Limited {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let space = self.limit - self.out.len();
+        if s.len() <= space {
+            self.out.push_str(s);
+            Ok(())
+        } else {
+            self.out.push_str(&s[..floor_char_boundary(s, space)]);
+            Err(fmt::Error)
+        }
+    }
+}
+
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn write_any(w: &mut dyn Write, any: &Any) -> fmt::Result {
+    let k = unsafe { &*any.k_ptr() };
+    write_attribute(w, k)?;
+    match k.t {
+        MIXED_LIST => write_general_list(w, k),
+        DICT => write_dict(w, k),
+        TABLE => {
+            w.write_char('+')?;
+            write_dict(w, unsafe { &*k.union.k0 })
+        }
+        ERROR => write!(w, "'{}", unsafe { CStr::from_ptr(k.union.s) }.to_string_lossy()),
+        GENERIC_NULL => w.write_str("::"),
+        t if t.is_simple_list() => {
+            let list = unsafe { &k.union.list };
+            write_simple(w, t, list.g0.as_ptr(), list.n as usize, false)
+        }
+        t if t.as_list().is_simple_list() => {
+            let data = if t == GUID_ATOM {
+                unsafe { k.union.list.g0.as_ptr() }
+            } else {
+                &k.union as *const _ as *const u8
+            };
+            write_simple(w, t.as_list(), data, 1, true)
+        }
+        t => write!(w, "<{}>", t),
+    }
+}
+
+fn write_attribute(w: &mut dyn Write, k: &K) -> fmt::Result {
+    match k.u {
+        1 => w.write_str("`s#"),
+        2 => w.write_str("`u#"),
+        3 => w.write_str("`p#"),
+        5 => w.write_str("`g#"),
+        _ => Ok(()),
+    }
+}
+
+fn write_general_list(w: &mut dyn Write, k: &K) -> fmt::Result {
+    let items = unsafe { std::slice::from_raw_parts(k.union.list.g0.as_ptr() as *const &Any, k.union.list.n as usize) };
+    match items {
+        [] => w.write_str("()"),
+        [item] => {
+            w.write_char(',')?;
+            write_any(w, item)
+        }
+        items => {
+            w.write_char('(')?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(';')?;
+                }
+                write_any(w, item)?;
+            }
+            w.write_char(')')
+        }
+    }
+}
+
+fn write_dict(w: &mut dyn Write, k: &K) -> fmt::Result {
+    let (keys, values) = unsafe { (&*(k.union.dict.k as *const Any), &*(k.union.dict.v as *const Any)) };
+    // The keys need brackets if they would otherwise swallow the `!`, since q evaluates right to left.
+    let keys_k = unsafe { &*keys.k_ptr() };
+    let brackets = keys_k.t == DICT
+        || keys_k.t == TABLE
+        || keys_k.u != 0
+        || keys.list_len() == Some(1)
+        || (keys_k.t == SYMBOL_LIST && !symbols(keys_k).iter().all(|s| is_bare_symbol(s)));
+    if brackets {
+        w.write_char('(')?;
+    }
+    write_any(w, keys)?;
+    if brackets {
+        w.write_char(')')?;
+    }
+    w.write_char('!')?;
+    write_any(w, values)
+}
+
+/// Writes an atom, or a simple list of `n` items of type `t` starting at `data`.
+fn write_simple(w: &mut dyn Write, t: KTypeCode, data: *const u8, n: usize, atom: bool) -> fmt::Result {
+    if !atom {
+        match n {
+            0 if t == CHAR_LIST => return w.write_str("\"\""),
+            0 => return write!(w, "`{}$()", type_name(t)),
+            1 => w.write_char(',')?,
+            _ => {}
+        }
+    }
+    unsafe {
+        match t {
+            BOOLEAN_LIST => {
+                for i in 0..n {
+                    w.write_char(if *data.add(i) != 0 { '1' } else { '0' })?;
+                }
+                w.write_char('b')
+            }
+            BYTE_LIST => {
+                w.write_str("0x")?;
+                for i in 0..n {
+                    write!(w, "{:02x}", *data.add(i))?;
+                }
+                Ok(())
+            }
+            CHAR_LIST => {
+                w.write_char('"')?;
+                for i in 0..n {
+                    write_char_escaped(w, *data.add(i))?;
+                }
+                w.write_char('"')
+            }
+            SYMBOL_LIST => {
+                let symbols = symbol_bytes(data, n);
+                if symbols.iter().all(|s| is_bare_symbol(s)) {
+                    for s in symbols {
+                        w.write_char('`')?;
+                        w.write_str(&String::from_utf8_lossy(s))?;
+                    }
+                    Ok(())
+                } else {
+                    // Symbols that can't be written after a backtick are cast from strings, as in `$"a b".
+                    w.write_str("`$")?;
+                    if n > 1 {
+                        w.write_char('(')?;
+                    }
+                    for (i, s) in symbols.iter().enumerate() {
+                        if i > 0 {
+                            w.write_char(';')?;
+                        }
+                        if s.len() == 1 && n > 1 {
+                            w.write_char(',')?;
+                        }
+                        w.write_char('"')?;
+                        for &c in s.iter() {
+                            write_char_escaped(w, c)?;
+                        }
+                        w.write_char('"')?;
+                    }
+                    if n > 1 {
+                        w.write_char(')')?;
+                    }
+                    Ok(())
+                }
+            }
+            GUID_LIST => {
+                for i in 0..n {
+                    if i > 0 {
+                        w.write_char(' ')?;
+                    }
+                    write_guid(w, std::slice::from_raw_parts(data.add(i * 16), 16))?;
+                }
+                Ok(())
+            }
+            _ => {
+                let tokens: Vec<String> = (0..n).map(|i| token(t, data, i)).collect();
+                w.write_str(&tokens.join(" "))?;
+                match suffix(t, &tokens) {
+                    Some(c) => w.write_char(c),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// The text of `n` symbols starting at `data`.
+unsafe fn symbol_bytes<'a>(data: *const u8, n: usize) -> Vec<&'a [u8]> {
+    (0..n)
+        .map(|i| CStr::from_ptr(*(data as *const *const i8).add(i)).to_bytes())
+        .collect()
+}
+
+fn symbols(k: &K) -> Vec<&[u8]> {
+    unsafe { symbol_bytes(k.union.list.g0.as_ptr(), k.union.list.n as usize) }
+}
+
+/// Returns true if a symbol can be written as a backtick followed by its text.
+fn is_bare_symbol(s: &[u8]) -> bool {
+    s.iter()
+        .all(|&c| c.is_ascii_alphanumeric() || matches!(c, b'.' | b'_' | b':' | b'/'))
+}
+
+/// The text of a numeric or temporal atom without any type suffix, as the console shows it in tables.
+pub(crate) fn bare_atom(any: &Any) -> Option<String> {
+    let k = unsafe { &*any.k_ptr() };
+    let t = k.t.as_list();
+    if i32::from(k.t) < 0 && matches!(i32::from(t), 5..=9 | 12..=19) {
+        Some(unsafe { token(t, &k.union as *const _ as *const u8, 0) })
+    } else {
+        None
+    }
+}
+
+/// Returns true if a token is a null or infinity written without its type character.
+fn is_special(token: &str) -> bool {
+    matches!(token, "0N" | "0W" | "-0W")
+}
+
+fn integer(v: i64, max: i64) -> String {
+    match v {
+        v if v == -max - 1 => "0N".to_owned(),
+        v if v == max => "0W".to_owned(),
+        v if v == -max => "-0W".to_owned(),
+        v => v.to_string(),
+    }
+}
+
+/// Converts the display form of a temporal value to a list item, removing the type character from nulls,
+/// infinities and months.
+fn temporal(value: impl fmt::Display, t: KTypeCode) -> String {
+    let mut s = value.to_string();
+    if t == MONTH_LIST || is_special(&s[..s.len() - 1]) {
+        s.pop();
+    }
+    s
+}
+
+/// Writes item `i` of a numeric or temporal list without any type suffix.
+unsafe fn token(t: KTypeCode, data: *const u8, i: usize) -> String {
+    match t {
+        SHORT_LIST => integer((*(data as *const i16).add(i)).into(), i16::MAX.into()),
+        INT_LIST => integer((*(data as *const i32).add(i)).into(), i32::MAX.into()),
+        LONG_LIST => integer(*(data as *const i64).add(i), i64::MAX),
+        REAL_LIST => match float((*(data as *const f32).add(i)).into()) {
+            // Reals use the upper case forms, as `0ne` and `0we` aren't valid q.
+            s if s.ends_with('n') || s.ends_with('w') => s.to_uppercase(),
+            s => s,
+        },
+        FLOAT_LIST => float(*(data as *const f64).add(i)),
+        TIMESTAMP_LIST => temporal(*(data as *const Timestamp).add(i), t),
+        MONTH_LIST => temporal(*(data as *const Month).add(i), t),
+        DATE_LIST => temporal(*(data as *const Date).add(i), t),
+        DATE_TIME_LIST => temporal(*(data as *const DateTime).add(i), t),
+        TIMESPAN_LIST => temporal(*(data as *const Timespan).add(i), t),
+        MINUTE_LIST => temporal(*(data as *const Minute).add(i), t),
+        SECOND_LIST => temporal(*(data as *const Second).add(i), t),
+        TIME_LIST => temporal(*(data as *const Time).add(i), t),
+        _ => unreachable!("not a numeric or temporal type: {}", t),
+    }
+}
+
+/// The type suffix needed for q to read `tokens` back as a list of type `t`.
+fn suffix(t: KTypeCode, tokens: &[String]) -> Option<char> {
+    let all_special = tokens.iter().all(|t| is_special(t));
+    match t {
+        SHORT_LIST => Some('h'),
+        INT_LIST => Some('i'),
+        REAL_LIST => Some('e'),
+        FLOAT_LIST
+            if tokens
+                .iter()
+                .all(|t| t.bytes().all(|b| b.is_ascii_digit() || b == b'-')) =>
+        {
+            Some('f')
+        }
+        MONTH_LIST => Some('m'),
+        TIMESTAMP_LIST if all_special => Some('p'),
+        DATE_LIST if all_special => Some('d'),
+        DATE_TIME_LIST if all_special => Some('z'),
+        TIMESPAN_LIST if all_special => Some('n'),
+        MINUTE_LIST if all_special => Some('u'),
+        SECOND_LIST if all_special => Some('v'),
+        TIME_LIST if all_special => Some('t'),
+        _ => None,
+    }
+}
+
+/// Formats a float to `FLOAT_PRECISION` significant digits, in the same way as C's `%g`.
+fn float(v: f64) -> String {
+    if v.is_nan() {
+        return "0n".to_owned();
+    }
+    if v.is_infinite() {
+        return if v < 0.0 { "-0w" } else { "0w" }.to_owned();
+    }
+    let scientific = format!("{:.*e}", (FLOAT_PRECISION - 1) as usize, v);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if !(-4..FLOAT_PRECISION).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim_fraction(mantissa), sign, exponent.abs())
+    } else {
+        let fixed = format!("{:.*}", (FLOAT_PRECISION - 1 - exponent) as usize, v);
+        trim_fraction(&fixed).to_owned()
+    }
+}
+
+fn trim_fraction(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+fn write_char_escaped(w: &mut dyn Write, c: u8) -> fmt::Result {
+    match c {
+        b'"' => w.write_str("\\\""),
+        b'\\' => w.write_str("\\\\"),
+        b'\n' => w.write_str("\\n"),
+        b'\r' => w.write_str("\\r"),
+        b'\t' => w.write_str("\\t"),
+        0x20..=0x7e => w.write_char(c as char),
+        c => write!(w, "\\{:03o}", c),
+    }
+}
+
+fn write_guid(w: &mut dyn Write, bytes: &[u8]) -> fmt::Result {
+    for (i, b) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            w.write_char('-')?;
+        }
+        write!(w, "{:02x}", b)?;
+    }
+    Ok(())
+}
+
+fn type_name(t: KTypeCode) -> &'static str {
+    match t {
+        BOOLEAN_LIST => "boolean",
+        GUID_LIST => "guid",
+        BYTE_LIST => "byte",
+        SHORT_LIST => "short",
+        INT_LIST => "int",
+        LONG_LIST => "long",
+        REAL_LIST => "real",
+        FLOAT_LIST => "float",
+        CHAR_LIST => "char",
+        SYMBOL_LIST => "symbol",
+        TIMESTAMP_LIST => "timestamp",
+        MONTH_LIST => "month",
+        DATE_LIST => "date",
+        DATE_TIME_LIST => "datetime",
+        TIMESPAN_LIST => "timespan",
+        MINUTE_LIST => "minute",
+        SECOND_LIST => "second",
+        TIME_LIST => "time",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list, parse_literal, symbol, Dict, Dictionary, KBox, List, Symbol};
+
+    fn q(s: &str) -> String {
+        to_q_string(&parse_literal(s).unwrap())
+    }
+
+    #[test]
+    fn renders_atoms_with_type_suffixes() {
+        assert_eq!(q("42"), "42");
+        assert_eq!(q("42h"), "42h");
+        assert_eq!(q("0Ni"), "0Ni");
+        assert_eq!(q("-0W"), "-0W");
+        assert_eq!(q("2f"), "2f");
+        assert_eq!(q("1.5"), "1.5");
+        assert_eq!(q("0n"), "0n");
+        assert_eq!(q("1.5e"), "1.5e");
+        assert_eq!(q("0Ne"), "0Ne");
+        assert_eq!(q("1b"), "1b");
+        assert_eq!(q("0x2a"), "0x2a");
+        assert_eq!(q("\"a\""), "\"a\"");
+        assert_eq!(q("`abc"), "`abc");
+        assert_eq!(q("2020.01m"), "2020.01m");
+        assert_eq!(q("0Nd"), "0Nd");
+        assert_eq!(q("2000.01.01T12:00:00.000"), "2000.01.01T12:00:00.000");
+        assert_eq!(q("::"), "::");
+    }
+
+    #[test]
+    fn renders_floats_to_seven_significant_figures() {
+        assert_eq!(float(1.0 / 3.0), "0.3333333");
+        assert_eq!(float(1234567.8), "1234568");
+        assert_eq!(float(1e10), "1e+10");
+        assert_eq!(float(0.0001), "0.0001");
+        assert_eq!(float(-0.000012345), "-1.2345e-05");
+    }
+
+    #[test]
+    fn renders_simple_lists() {
+        assert_eq!(q("1 2 3i"), "1 2 3i");
+        assert_eq!(q("1 2 3f"), "1 2 3f");
+        assert_eq!(q("1.5 2"), "1.5 2");
+        assert_eq!(q("0110b"), "0110b");
+        assert_eq!(q("0x0102"), "0x0102");
+        assert_eq!(q("\"a\\nb\\\"\""), "\"a\\nb\\\"\"");
+        assert_eq!(q("`a`b"), "`a`b");
+        assert_eq!(q("enlist `a"), ",`a");
+        assert_eq!(q("`a.b`:c/d`"), "`a.b`:c/d`");
+        assert_eq!(q("enlist 1"), ",1");
+        assert_eq!(q("2000.01 2000.02m"), "2000.01 2000.02m");
+        assert_eq!(q("2020.01.01 0Nd"), "2020.01.01 0N");
+        assert_eq!(q("0N 0Nd"), "0N 0Nd");
+        assert_eq!(to_q_string(KBox::<List<i32>>::new_list().as_ref()), "`int$()");
+        assert_eq!(to_q_string(KBox::<List<i8>>::new_list().as_ref()), "\"\"");
+    }
+
+    #[test]
+    fn renders_general_lists_dictionaries_and_tables() {
+        assert_eq!(q("(1;`a;\"bc\";(2 3;::))"), "(1;`a;\"bc\";(2 3;::))");
+        assert_eq!(q("()"), "()");
+        assert_eq!(q("`a`b!1 2"), "`a`b!1 2");
+        assert_eq!(q("(enlist `a)!enlist 1"), "(,`a)!,1");
+        assert_eq!(q("([] a:1 2; b:`x`y)"), "+`a`b!(1 2;`x`y)");
+        assert_eq!(q("([k:`x`y] v:1 2)"), "(+(,`k)!,`x`y)!+(,`v)!,1 2");
+    }
+
+    #[test]
+    fn casts_symbols_that_need_quoting() {
+        let symbols = |s: &[&str]| to_q_string(s.iter().map(|s| symbol(s)).collect::<KBox<List<Symbol>>>().as_ref());
+        assert_eq!(to_q_string(KBox::new_atom(symbol("a b")).as_ref()), "`$\"a b\"");
+        assert_eq!(symbols(&["a b", "cd"]), "`$(\"a b\";\"cd\")");
+        assert_eq!(symbols(&["a b", "c"]), "`$(\"a b\";,\"c\")");
+        assert_eq!(symbols(&["a\"b"]), ",`$\"a\\\"b\"");
+        assert_eq!(symbols(&["a", "b"]), "`a`b");
+        let keys: KBox<List<Symbol>> = vec![symbol("a b"), symbol("c")].into_iter().collect();
+        let d = KBox::<Dict<Symbol, i64>>::from_lists(keys, list![i64; 1, 2]).unwrap();
+        assert_eq!(
+            to_q_string(KBox::<Dictionary>::from(d).as_ref()),
+            "(`$(\"a b\";,\"c\"))!1 2"
+        );
+    }
+
+    #[test]
+    fn truncates_to_max_length() {
+        let list = parse_literal("1 2 3 4 5 6 7 8").unwrap();
+        let mut s = String::new();
+        list.fmt_q(&mut s, Some(10)).unwrap();
+        assert_eq!(s, "1 2 3 4 ..");
+
+        let mut s = String::new();
+        list.fmt_q(&mut s, Some(15)).unwrap();
+        assert_eq!(s, "1 2 3 4 5 6 7 8");
+    }
+}
@@ -0,0 +1,527 @@
+//! A serde `Serializer` that converts Rust values into K objects.
+//!
+//! * Primitive values become atoms. Unsigned types are widened to the next largest signed type, as KDB has no unsigned types
+//!   other than bytes.
+//! * Strings become char lists, byte arrays become byte lists.
+//! * `None` and `()` become the generic null `(::)`. In lists of atoms they are replaced with the null value for the type of the list.
+//! * Structs and maps become dictionaries. Struct fields and string map keys are converted to symbols.
+//! * Sequences of atoms with the same type become simple lists, sequences of structs (or maps) with the same keys become
+//!   tables, and any other sequence becomes a mixed list.
+//! * Unit enum variants become symbols, other variants become a dictionary with a single key: the variant name.
+use crate::any::{generic_null, Any};
+use crate::error::ConversionError;
+use crate::k::K;
+use crate::k_type::{CHAR_LIST, DICT, SYMBOL_LIST};
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::list::{list_item, simple_list_from_atoms, List};
+use crate::symbol::Symbol;
+use crate::table::Table;
+use crate::type_traits::KObject;
+use serde::ser::{self, Serialize};
+
+impl ser::Error for ConversionError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ConversionError::Custom(msg.to_string())
+    }
+}
+
+/// Serializes a Rust value into a K object.
+///
+/// # Example
+/// ```
+/// use kdb::{cast, to_any, List};
+///
+/// let k = to_any(&vec![1i64, 2, 3]).unwrap();
+/// assert_eq!(cast!(k; List<i64>).as_slice(), &[1, 2, 3]);
+/// ```
+pub fn to_any<T: Serialize + ?Sized>(value: &T) -> Result<KBox<Any>, ConversionError> {
+    value.serialize(Serializer)
+}
+
+/// A serde `Serializer` that creates K objects.
+pub struct Serializer;
+
+fn to_symbol(s: &str) -> Result<Symbol, ConversionError> {
+    Symbol::new(s).map_err(|e| ConversionError::Custom(e.to_string()))
+}
+
+/// Builds a list from a set of items, collapsing it to a simple list where possible.
+fn build_list(items: Vec<KBox<Any>>) -> KBox<Any> {
+    simple_list_from_atoms(&items).unwrap_or_else(|| items.into_iter().collect::<KBox<List<Any>>>().into())
+}
+
+fn build_dict(keys: KBox<Any>, values: KBox<Any>) -> KBox<Any> {
+    unsafe { KBox::from_raw(kapi::xD(keys.into_raw() as *const K, values.into_raw() as *const K) as *mut K) }
+}
+
+/// Creates a dictionary with a single symbol key, used for enum variants that hold data.
+fn variant_dict(variant: &'static str, value: KBox<Any>) -> Result<KBox<Any>, ConversionError> {
+    let keys: KBox<List<Symbol>> = std::iter::once(to_symbol(variant)?).collect();
+    let values: KBox<List<Any>> = std::iter::once(value).collect();
+    Ok(build_dict(keys.into(), values.into()))
+}
+
+/// The symbol keys of a dictionary, if they are stored as a symbol list.
+fn symbol_keys(any: &Any) -> Option<&List<Symbol>> {
+    if any.k_type() != DICT {
+        return None;
+    }
+    let keys = unsafe { &*((*any.k_ptr()).union.dict.k as *const Any) };
+    if keys.k_type() == SYMBOL_LIST {
+        Some(unsafe { &*(keys as *const Any as *const List<Symbol>) })
+    } else {
+        None
+    }
+}
+
+/// Builds a table if every item is a dictionary with the same symbol keys.
+fn try_build_table(rows: &[KBox<Any>]) -> Option<KBox<Any>> {
+    let names = symbol_keys(rows.first()?)?;
+    if names.is_empty()
+        || rows
+            .iter()
+            .any(|r| symbol_keys(r).map(|k| k.as_slice()) != Some(names.as_slice()))
+    {
+        return None;
+    }
+    let columns: KBox<List<Any>> = (0..names.len())
+        .map(|c| {
+            build_list(
+                rows.iter()
+                    .map(|r| unsafe { list_item((*r.k_ptr()).union.dict.v, c) })
+                    .collect(),
+            )
+        })
+        .collect();
+    let names: KBox<List<Symbol>> = names.iter().copied().collect();
+    KBox::<Table>::new_table(names, columns).ok().map(Into::into)
+}
+
+/// Serializes items of sequences and tuples.
+pub struct SerializeVec {
+    items: Vec<KBox<Any>>,
+}
+
+/// Serializes tuple variants.
+pub struct SerializeTupleVariant {
+    variant: &'static str,
+    items: Vec<KBox<Any>>,
+}
+
+/// Serializes maps.
+pub struct SerializeMap {
+    keys: Vec<KBox<Any>>,
+    values: Vec<KBox<Any>>,
+}
+
+/// Serializes structs.
+pub struct SerializeStruct {
+    keys: Vec<Symbol>,
+    values: Vec<KBox<Any>>,
+}
+
+/// Serializes struct variants.
+pub struct SerializeStructVariant {
+    variant: &'static str,
+    inner: SerializeStruct,
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    fn serialize_bool(self, v: bool) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<KBox<Any>, ConversionError> {
+        // i8 maps to a KDB char, so use a short to keep it numeric.
+        Ok((v as i16).into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<KBox<Any>, ConversionError> {
+        Ok((v as i32).into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<KBox<Any>, ConversionError> {
+        Ok((v as i64).into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<KBox<Any>, ConversionError> {
+        if v > i64::MAX as u64 {
+            return Err(ConversionError::Custom(format!("{} is too large for a KDB long", v)));
+        }
+        Ok((v as i64).into())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<KBox<Any>, ConversionError> {
+        if v.is_ascii() {
+            Ok((v as u8 as i8).into())
+        } else {
+            self.serialize_str(v.encode_utf8(&mut [0; 4]))
+        }
+    }
+
+    fn serialize_str(self, v: &str) -> Result<KBox<Any>, ConversionError> {
+        Ok(unsafe { KBox::from_raw(kapi::kpn(v.as_ptr() as *const i8, v.len() as i64)) })
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<KBox<Any>, ConversionError> {
+        Ok(v.iter().copied().collect::<KBox<List<u8>>>().into())
+    }
+
+    fn serialize_none(self) -> Result<KBox<Any>, ConversionError> {
+        Ok(generic_null())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<KBox<Any>, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<KBox<Any>, ConversionError> {
+        Ok(generic_null())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<KBox<Any>, ConversionError> {
+        Ok(generic_null())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<KBox<Any>, ConversionError> {
+        Ok(to_symbol(variant)?.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<KBox<Any>, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<KBox<Any>, ConversionError> {
+        variant_dict(variant, value.serialize(self)?)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, ConversionError> {
+        Ok(SerializeVec {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, ConversionError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeVec, ConversionError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeTupleVariant, ConversionError> {
+        Ok(SerializeTupleVariant {
+            variant,
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, ConversionError> {
+        Ok(SerializeMap {
+            keys: Vec::with_capacity(len.unwrap_or(0)),
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeStruct, ConversionError> {
+        Ok(SerializeStruct {
+            keys: Vec::with_capacity(len),
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeStructVariant, ConversionError> {
+        Ok(SerializeStructVariant {
+            variant,
+            inner: self.serialize_struct(variant, len)?,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        Ok(try_build_table(&self.items).unwrap_or_else(|| build_list(self.items)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        variant_dict(self.variant, build_list(self.items))
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ConversionError> {
+        let key = key.serialize(Serializer)?;
+        // String keys are stored as symbols, as they would be in q.
+        let key = if key.k_type() == CHAR_LIST {
+            let s = unsafe { &*(key.k_ptr() as *const List<i8>) }.try_as_str()?;
+            to_symbol(s)?.into()
+        } else {
+            key
+        };
+        self.keys.push(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.values.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        Ok(build_dict(build_list(self.keys), build_list(self.values)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeStruct {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConversionError> {
+        self.keys.push(to_symbol(key)?);
+        self.values.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        let keys: KBox<List<Symbol>> = self.keys.into_iter().collect();
+        Ok(build_dict(keys.into(), build_list(self.values)))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = KBox<Any>;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConversionError> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<KBox<Any>, ConversionError> {
+        variant_dict(self.variant, ser::SerializeStruct::end(self.inner)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::__private::get_field;
+    use crate::k_type::GENERIC_NULL;
+    use crate::{cast, symbol, Atom, Dictionary};
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Trade {
+        sym: &'static str,
+        price: f64,
+        size: Option<i64>,
+    }
+
+    #[derive(Serialize)]
+    enum Message {
+        Heartbeat,
+        Quote(f64, f64),
+        Fill { id: i64 },
+    }
+
+    #[test]
+    fn primitives_serialize_to_atoms() {
+        assert_eq!(cast!(to_any(&42i32).unwrap(); Atom<i32>).value(), 42);
+        assert_eq!(cast!(to_any(&42u16).unwrap(); Atom<i32>).value(), 42);
+        assert!(cast!(to_any(&true).unwrap(); Atom<bool>).value());
+        assert_eq!(cast!(to_any(&'x').unwrap(); Atom<i8>).value(), b'x' as i8);
+        assert_eq!(cast!(to_any("hello").unwrap(); List<i8>).try_as_str().unwrap(), "hello");
+        assert_eq!(to_any(&None::<i32>).unwrap().k_type(), GENERIC_NULL);
+        assert!(to_any(&u64::MAX).is_err());
+    }
+
+    #[test]
+    fn sequences_of_atoms_serialize_to_simple_lists() {
+        let k = to_any(&vec![Some(1i64), None, Some(3)]).unwrap();
+        assert_eq!(cast!(k; List<i64>).as_slice(), &[1, i64::MIN, 3]);
+
+        let k = to_any(&(1i32, "a")).unwrap();
+        assert_eq!(cast!(k; List<Any>).len(), 2);
+    }
+
+    #[test]
+    fn structs_serialize_to_dictionaries() {
+        let k = to_any(&Trade {
+            sym: "abc",
+            price: 1.5,
+            size: None,
+        })
+        .unwrap();
+        let dict = cast!(k; Dictionary);
+        assert_eq!(dict.len(), 3);
+        assert_eq!(get_field::<f64>(&dict, "price").unwrap(), 1.5);
+        assert_eq!(get_field::<Option<i64>>(&dict, "size").unwrap(), None);
+    }
+
+    #[test]
+    fn maps_serialize_to_dictionaries_with_symbol_keys() {
+        let mut map = BTreeMap::new();
+        map.insert("a", 1i32);
+        map.insert("b", 2i32);
+        let k = to_any(&map).unwrap();
+        let dict = cast!(k; Dictionary);
+        assert_eq!(get_field::<i32>(&dict, "b").unwrap(), 2);
+    }
+
+    #[test]
+    fn sequences_of_structs_serialize_to_tables() {
+        let trades = vec![
+            Trade {
+                sym: "a",
+                price: 1.5,
+                size: Some(100),
+            },
+            Trade {
+                sym: "b",
+                price: 2.5,
+                size: None,
+            },
+        ];
+        let table = cast!(to_any(&trades).unwrap(); Table);
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.column_names().as_slice(),
+            &[symbol("sym"), symbol("price"), symbol("size")]
+        );
+        assert_eq!(
+            cast!(table.column(symbol("price")).unwrap(); List<f64>).as_slice(),
+            &[1.5, 2.5]
+        );
+        assert_eq!(
+            cast!(table.column(symbol("size")).unwrap(); List<i64>).as_slice(),
+            &[100, i64::MIN]
+        );
+        let syms = cast!(table.column(symbol("sym")).unwrap(); List<Any>);
+        assert_eq!(cast!(&syms[1]; List<i8>).try_as_str().unwrap(), "b");
+    }
+
+    #[test]
+    fn enums_serialize_to_symbols_and_dictionaries() {
+        let k = to_any(&Message::Heartbeat).unwrap();
+        assert_eq!(cast!(k; Atom<Symbol>).value(), symbol("Heartbeat"));
+
+        let k = to_any(&Message::Quote(1.0, 2.0)).unwrap();
+        let dict = cast!(k; Dictionary);
+        assert_eq!(get_field::<Vec<f64>>(&dict, "Quote").unwrap(), vec![1.0, 2.0]);
+
+        let k = to_any(&Message::Fill { id: 7 }).unwrap();
+        let dict = cast!(k; Dictionary);
+        let fill = dict.values()[0].as_ref();
+        assert_eq!(get_field::<i64>(cast!(fill; Dictionary), "id").unwrap(), 7);
+    }
+}
@@ -48,19 +48,19 @@ mod tests {
     fn b9_d9_roundtrips() {
         let l = list![i32; 1, 2, 3];
 
-        let bytes = b9_serialize(SerializationMode::InProc, l.as_ref()).unwrap();
+        let bytes = b9_serialize(SerializationMode::InProc, &l).unwrap();
         let v = cast!(d9_deserialize(bytes).unwrap(); List<i32>);
         assert_eq!(v.as_slice(), &[1, 2, 3]);
 
-        let bytes = b9_serialize(SerializationMode::Enumerate, l.as_ref()).unwrap();
+        let bytes = b9_serialize(SerializationMode::Enumerate, &l).unwrap();
         let v = cast!(d9_deserialize(bytes).unwrap(); List<i32>);
         assert_eq!(v.as_slice(), &[1, 2, 3]);
 
-        let bytes = b9_serialize(SerializationMode::Unenumerate, l.as_ref()).unwrap();
+        let bytes = b9_serialize(SerializationMode::Unenumerate, &l).unwrap();
         let v = cast!(d9_deserialize(bytes).unwrap(); List<i32>);
         assert_eq!(v.as_slice(), &[1, 2, 3]);
 
-        let bytes = b9_serialize(SerializationMode::Compress, l.as_ref()).unwrap();
+        let bytes = b9_serialize(SerializationMode::Compress, &l).unwrap();
         let v = cast!(d9_deserialize(bytes).unwrap(); List<i32>);
         assert_eq!(v.as_slice(), &[1, 2, 3]);
     }
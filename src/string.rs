@@ -0,0 +1,121 @@
+//! Conversions between char lists and Rust strings.
+use crate::error::ConversionError;
+use crate::kapi;
+use crate::kbox::KBox;
+use crate::list::List;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::{fmt, slice, str};
+
+impl List<i8> {
+    /// The characters of the list as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        let chars = self.as_slice();
+        unsafe { slice::from_raw_parts(chars.as_ptr() as *const u8, chars.len()) }
+    }
+
+    /// Converts the list to a string, replacing any invalid UTF-8 with `U+FFFD`. This only allocates if the
+    /// list isn't valid UTF-8.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
+}
+
+impl KBox<List<i8>> {
+    /// Creates a char list holding a copy of a string, using `kpn`.
+    pub fn new_string(s: &str) -> Self {
+        unsafe { KBox::from_raw(kapi::kpn(s.as_ptr() as *const i8, s.len() as i64)) }
+    }
+}
+
+impl From<&str> for KBox<List<i8>> {
+    fn from(s: &str) -> Self {
+        KBox::new_string(s)
+    }
+}
+
+impl From<String> for KBox<List<i8>> {
+    fn from(s: String) -> Self {
+        KBox::new_string(&s)
+    }
+}
+
+/// Writes the characters of the list, replacing any invalid UTF-8 with `U+FFFD`.
+impl fmt::Display for List<i8> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.to_string_lossy())
+    }
+}
+
+/// Appends text to a char list, so that it can be used with `write!`.
+///
+/// # Example
+/// ```
+/// use kdb::{KBox, List};
+/// use std::fmt::Write;
+///
+/// let mut s = KBox::<List<i8>>::from("x = ");
+/// write!(s, "{}", 42).unwrap();
+/// assert_eq!(s.try_as_str().unwrap(), "x = 42");
+/// ```
+impl fmt::Write for KBox<List<i8>> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let chars = unsafe { slice::from_raw_parts(s.as_ptr() as *const i8, s.len()) };
+        self.extend_from_slice(chars);
+        Ok(())
+    }
+}
+
+impl TryFrom<&List<i8>> for String {
+    type Error = ConversionError;
+
+    /// Copies the list into a string, failing if it isn't valid UTF-8.
+    fn try_from(list: &List<i8>) -> Result<Self, Self::Error> {
+        Ok(list.try_as_str()?.to_owned())
+    }
+}
+
+impl TryFrom<KBox<List<i8>>> for String {
+    type Error = ConversionError;
+
+    /// Copies the list into a string, failing if it isn't valid UTF-8.
+    fn try_from(list: KBox<List<i8>>) -> Result<Self, Self::Error> {
+        String::try_from(&*list)
+    }
+}
+
+impl<'a> TryFrom<&'a List<i8>> for Cow<'a, str> {
+    type Error = ConversionError;
+
+    /// Borrows the list as a string, failing if it isn't valid UTF-8. Use `to_string_lossy` to replace invalid
+    /// characters instead.
+    fn try_from(list: &'a List<i8>) -> Result<Self, Self::Error> {
+        Ok(Cow::Borrowed(list.try_as_str()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn strings_round_trip_through_char_lists() {
+        let mut s = KBox::<List<i8>>::from("héllo");
+        assert_eq!(s.as_bytes(), "héllo".as_bytes());
+        let name = "world";
+        write!(s, ", {}", name).unwrap();
+        assert_eq!(String::try_from(&*s).unwrap(), "héllo, world");
+        assert_eq!(format!("[{:>14}]", s), "[  héllo, world]");
+        assert_eq!(KBox::<List<i8>>::from(String::new()).len(), 0);
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected_or_replaced() {
+        let s: KBox<List<i8>> = vec![b'a' as i8, -1, b'b' as i8].into();
+        assert!(String::try_from(&*s).is_err());
+        assert!(Cow::<str>::try_from(&*s).is_err());
+        assert_eq!(s.to_string_lossy(), "a\u{FFFD}b");
+        assert_eq!(s.to_string(), "a\u{FFFD}b");
+    }
+}
@@ -1,11 +1,90 @@
-use crate::{k::K, type_traits::KObject};
+use crate::any::Any;
+use crate::error::ConversionError;
+use crate::k_type::{KTypeCode, MIXED_LIST, TABLE};
+use crate::kbox::KBox;
+use crate::list::{check_mixed_items, List};
+use crate::symbol::Symbol;
+use crate::type_traits::{KListable, KTyped};
+use crate::{k::K, kapi, type_traits::KObject};
 
 /// Represents a table (a dictionary of columns) in KDB
 #[repr(transparent)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct Table {
     k: K,
 }
 
+impl Table {
+    fn dict(&self) -> &K {
+        unsafe { &*self.k.union.k0 }
+    }
+
+    /// The names of the columns in the table.
+    #[inline]
+    pub fn column_names(&self) -> &List<Symbol> {
+        unsafe { &*(self.dict().union.dict.k as *const List<Symbol>) }
+    }
+
+    /// The columns in the table. Each column is a list with one item for each row of the table.
+    #[inline]
+    pub fn columns(&self) -> &List<Any> {
+        unsafe { &*(self.dict().union.dict.v as *const List<Any>) }
+    }
+
+    /// Gets a column by name.
+    pub fn column(&self, name: Symbol) -> Option<&Any> {
+        let index = self.column_names().iter().position(|n| *n == name)?;
+        Some(&self.columns()[index])
+    }
+
+    /// The number of rows in the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.columns().iter().next().and_then(|c| c.list_len()).unwrap_or(0)
+    }
+
+    /// Returns true if the table has no rows.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl KBox<Table> {
+    /// Creates a new table from a list of column names and a list of columns.
+    /// Every column must be a list, and all the columns must be the same length.
+    pub fn new_table(names: KBox<List<Symbol>>, columns: KBox<List<Any>>) -> Result<Self, ConversionError> {
+        if names.len() != columns.len() {
+            return Err(ConversionError::InvalidLength {
+                expected: names.len(),
+                found: columns.len(),
+            });
+        }
+        let rows = columns.iter().next().and_then(|c| c.list_len()).unwrap_or(0);
+        for column in columns.iter() {
+            match column.list_len() {
+                Some(len) if len == rows => {}
+                Some(len) => {
+                    return Err(ConversionError::InvalidLength {
+                        expected: rows,
+                        found: len,
+                    })
+                }
+                None => {
+                    return Err(ConversionError::InvalidKCast {
+                        from: column.k_type(),
+                        to: MIXED_LIST,
+                    })
+                }
+            }
+        }
+        unsafe {
+            let dict = kapi::xD(names.into_raw() as *const K, columns.into_raw() as *const K);
+            Ok(KBox::from_raw(kapi::xT(dict) as *mut K))
+        }
+    }
+}
+
 impl KObject for Table {
     #[inline]
     fn k_ptr(&self) -> *const K {
@@ -17,3 +96,46 @@ impl KObject for Table {
         &mut self.k
     }
 }
+
+impl KTyped for Table {
+    const K_TYPE: KTypeCode = TABLE;
+}
+
+/// A list of tables is stored as a mixed list.
+impl KListable for Table {
+    const LIST_TYPE_CODE: KTypeCode = MIXED_LIST;
+    type ListItem = KBox<Table>;
+
+    unsafe fn join_to(item: Self::ListItem, mut k: *mut K) -> *mut K {
+        kapi::jk(&mut k, item.into_raw() as *const K)
+    }
+
+    fn check_items(list: &K) -> Result<(), ConversionError> {
+        check_mixed_items::<Table>(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list, symbol};
+
+    #[test]
+    fn new_table_creates_table_from_columns() {
+        let names = list![Symbol; symbol("a"), symbol("b")];
+        let columns = list![Any; list![i32; 1, 2, 3], list![f64; 1.5, 2.5, 3.5]];
+        let table = KBox::new_table(names, columns).unwrap();
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.column_names().as_slice(), &[symbol("a"), symbol("b")]);
+        let b = crate::cast!(table.column(symbol("b")).unwrap(); List<f64>);
+        assert_eq!(b.as_slice(), &[1.5, 2.5, 3.5]);
+    }
+
+    #[test]
+    fn new_table_rejects_columns_of_different_lengths() {
+        let names = list![Symbol; symbol("a"), symbol("b")];
+        let columns = list![Any; list![i32; 1, 2, 3], list![f64; 1.5]];
+        assert!(KBox::new_table(names, columns).is_err());
+    }
+}
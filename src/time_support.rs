@@ -0,0 +1,326 @@
+//! Conversions between the KDB temporal types and the types in the `time` crate.
+//!
+//! Conversions are fallible wherever the two types cover different ranges. Null values fail to convert
+//! with `ConversionError::NullValue`, and the infinities `0W` and `-0W`, as well as values outside the range of the
+//! target type, fail with `ConversionError::OutOfRange`.
+use crate::date_time_types::*;
+use crate::error::ConversionError;
+use std::convert::TryFrom;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+/// The julian day number of 1 Jan 2000.
+const EPOCH_JULIAN_DAY: i32 = 2_451_545;
+
+fn epoch() -> PrimitiveDateTime {
+    PrimitiveDateTime::new(
+        time::Date::from_julian_day(EPOCH_JULIAN_DAY).unwrap(),
+        time::Time::MIDNIGHT,
+    )
+}
+
+/// Checks that a value is neither null nor one of the infinities `0W` and `-0W`, which have no equivalent in the
+/// `time` types.
+fn finite_i64(value: i64) -> Result<i64, ConversionError> {
+    match value {
+        i64::MIN => Err(ConversionError::NullValue),
+        value if value == i64::MAX || value == -i64::MAX => Err(ConversionError::OutOfRange),
+        value => Ok(value),
+    }
+}
+
+fn finite_i32(value: i32) -> Result<i32, ConversionError> {
+    match value {
+        i32::MIN => Err(ConversionError::NullValue),
+        value if value == i32::MAX || value == -i32::MAX => Err(ConversionError::OutOfRange),
+        value => Ok(value),
+    }
+}
+
+fn time_from_nanos(nanos: i64) -> Result<time::Time, ConversionError> {
+    if !(0..86_400_000_000_000).contains(&nanos) {
+        return Err(ConversionError::OutOfRange);
+    }
+    Ok(time::Time::MIDNIGHT + Duration::nanoseconds(nanos))
+}
+
+fn nanos_from_time(t: time::Time) -> i64 {
+    (t - time::Time::MIDNIGHT).whole_nanoseconds() as i64
+}
+
+impl TryFrom<Timestamp> for PrimitiveDateTime {
+    type Error = ConversionError;
+
+    fn try_from(ts: Timestamp) -> Result<PrimitiveDateTime, ConversionError> {
+        let nanos = finite_i64(ts.as_raw())?;
+        epoch()
+            .checked_add(Duration::nanoseconds(nanos))
+            .ok_or(ConversionError::OutOfRange)
+    }
+}
+
+impl TryFrom<PrimitiveDateTime> for Timestamp {
+    type Error = ConversionError;
+
+    fn try_from(dt: PrimitiveDateTime) -> Result<Timestamp, ConversionError> {
+        match i64::try_from((dt - epoch()).whole_nanoseconds()) {
+            Ok(nanos) if finite_i64(nanos).is_ok() => Ok(Timestamp::from_raw(nanos)),
+            _ => Err(ConversionError::OutOfRange),
+        }
+    }
+}
+
+/// Converts to a date and time in UTC.
+impl TryFrom<Timestamp> for OffsetDateTime {
+    type Error = ConversionError;
+
+    fn try_from(ts: Timestamp) -> Result<OffsetDateTime, ConversionError> {
+        Ok(PrimitiveDateTime::try_from(ts)?.assume_utc())
+    }
+}
+
+impl TryFrom<OffsetDateTime> for Timestamp {
+    type Error = ConversionError;
+
+    fn try_from(dt: OffsetDateTime) -> Result<Timestamp, ConversionError> {
+        let utc = dt.to_offset(UtcOffset::UTC);
+        Timestamp::try_from(PrimitiveDateTime::new(utc.date(), utc.time()))
+    }
+}
+
+impl TryFrom<Date> for time::Date {
+    type Error = ConversionError;
+
+    fn try_from(date: Date) -> Result<time::Date, ConversionError> {
+        let days = finite_i32(date.as_raw())?;
+        days.checked_add(EPOCH_JULIAN_DAY)
+            .and_then(|d| time::Date::from_julian_day(d).ok())
+            .ok_or(ConversionError::OutOfRange)
+    }
+}
+
+impl From<time::Date> for Date {
+    fn from(date: time::Date) -> Date {
+        Date::from(date.to_julian_day() - EPOCH_JULIAN_DAY)
+    }
+}
+
+/// Converts a month to the first day of that month.
+impl TryFrom<Month> for time::Date {
+    type Error = ConversionError;
+
+    fn try_from(month: Month) -> Result<time::Date, ConversionError> {
+        let m = finite_i32(i32::from(month))?;
+        let year = i32::try_from(2000 + i64::from(m).div_euclid(12)).map_err(|_| ConversionError::OutOfRange)?;
+        let month = time::Month::try_from(m.rem_euclid(12) as u8 + 1).unwrap();
+        time::Date::from_calendar_date(year, month, 1).map_err(|_| ConversionError::OutOfRange)
+    }
+}
+
+/// Converts to the month containing the specified date.
+impl From<time::Date> for Month {
+    fn from(date: time::Date) -> Month {
+        Month::from((date.year() - 2000) * 12 + date.month() as i32 - 1)
+    }
+}
+
+/// Converts a KDB datetime, which has millisecond precision.
+impl TryFrom<DateTime> for PrimitiveDateTime {
+    type Error = ConversionError;
+
+    fn try_from(dt: DateTime) -> Result<PrimitiveDateTime, ConversionError> {
+        let days = f64::from(dt);
+        if days.is_nan() {
+            return Err(ConversionError::NullValue);
+        }
+        let millis = (days * 86_400_000.0).round();
+        if !millis.is_finite() || millis.abs() >= i64::MAX as f64 {
+            return Err(ConversionError::OutOfRange);
+        }
+        epoch()
+            .checked_add(Duration::milliseconds(millis as i64))
+            .ok_or(ConversionError::OutOfRange)
+    }
+}
+
+/// Converts to a KDB datetime, which has millisecond precision.
+impl From<PrimitiveDateTime> for DateTime {
+    fn from(dt: PrimitiveDateTime) -> DateTime {
+        DateTime::from((dt - epoch()).whole_milliseconds() as f64 / 86_400_000.0)
+    }
+}
+
+impl TryFrom<Time> for time::Time {
+    type Error = ConversionError;
+
+    fn try_from(t: Time) -> Result<time::Time, ConversionError> {
+        let millis = finite_i32(i32::from(t))?;
+        time_from_nanos(i64::from(millis) * 1_000_000)
+    }
+}
+
+/// Converts to a KDB time, truncating to millisecond precision.
+impl From<time::Time> for Time {
+    fn from(t: time::Time) -> Time {
+        Time::from((nanos_from_time(t) / 1_000_000) as i32)
+    }
+}
+
+impl TryFrom<Second> for time::Time {
+    type Error = ConversionError;
+
+    fn try_from(s: Second) -> Result<time::Time, ConversionError> {
+        let secs = finite_i32(i32::from(s))?;
+        time_from_nanos(i64::from(secs) * 1_000_000_000)
+    }
+}
+
+/// Converts to a KDB second, truncating any fractional seconds.
+impl From<time::Time> for Second {
+    fn from(t: time::Time) -> Second {
+        Second::from((nanos_from_time(t) / 1_000_000_000) as i32)
+    }
+}
+
+impl TryFrom<Minute> for time::Time {
+    type Error = ConversionError;
+
+    fn try_from(m: Minute) -> Result<time::Time, ConversionError> {
+        let minutes = finite_i32(i32::from(m))?;
+        time_from_nanos(i64::from(minutes) * 60_000_000_000)
+    }
+}
+
+/// Converts to a KDB minute, truncating any seconds.
+impl From<time::Time> for Minute {
+    fn from(t: time::Time) -> Minute {
+        Minute::from((nanos_from_time(t) / 60_000_000_000) as i32)
+    }
+}
+
+impl TryFrom<Timespan> for Duration {
+    type Error = ConversionError;
+
+    fn try_from(span: Timespan) -> Result<Duration, ConversionError> {
+        Ok(Duration::nanoseconds(finite_i64(i64::from(span))?))
+    }
+}
+
+impl TryFrom<Duration> for Timespan {
+    type Error = ConversionError;
+
+    fn try_from(d: Duration) -> Result<Timespan, ConversionError> {
+        match i64::try_from(d.whole_nanoseconds()) {
+            Ok(nanos) if finite_i64(nanos).is_ok() => Ok(Timespan::from_nanos(nanos)),
+            _ => Err(ConversionError::DurationTooLong),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime, time};
+
+    #[test]
+    fn timestamps_convert_to_and_from_date_times() {
+        let dt = datetime!(1969-07-20 20:17:40.123456789);
+        let ts = Timestamp::try_from(dt).unwrap();
+        assert_eq!(PrimitiveDateTime::try_from(ts).unwrap(), dt);
+        assert!(Timestamp::try_from(datetime!(1000-01-01 0:00)).is_err());
+        assert!(PrimitiveDateTime::try_from(Timestamp::from_raw(i64::MIN)).is_err());
+        for inf in [i64::MAX, -i64::MAX] {
+            assert!(matches!(
+                PrimitiveDateTime::try_from(Timestamp::from_raw(inf)),
+                Err(ConversionError::OutOfRange)
+            ));
+        }
+
+        let offset = datetime!(2000-01-01 1:00 +1);
+        assert_eq!(Timestamp::try_from(offset).unwrap().as_raw(), 0);
+        assert_eq!(OffsetDateTime::try_from(Timestamp::from_raw(0)).unwrap(), offset);
+    }
+
+    #[test]
+    fn dates_and_months_convert_to_and_from_dates() {
+        let d = date!(1812 - 06 - 24);
+        assert_eq!(time::Date::try_from(Date::from(d)).unwrap(), d);
+        assert!(time::Date::try_from(Date::from(i32::MIN)).is_err());
+        assert!(matches!(
+            time::Date::try_from(Date::from(i32::MAX)),
+            Err(ConversionError::OutOfRange)
+        ));
+        assert!(matches!(
+            time::Date::try_from(Date::from(-i32::MAX)),
+            Err(ConversionError::OutOfRange)
+        ));
+        assert!(matches!(
+            time::Date::try_from(Month::from(i32::MIN)),
+            Err(ConversionError::NullValue)
+        ));
+        assert!(matches!(
+            time::Date::try_from(Month::from(i32::MAX)),
+            Err(ConversionError::OutOfRange)
+        ));
+
+        let month = Month::from(d);
+        assert_eq!(i32::from(month), (1812 - 2000) * 12 + 5);
+        assert_eq!(time::Date::try_from(month).unwrap(), date!(1812 - 06 - 01));
+    }
+
+    #[test]
+    fn date_times_convert_with_millisecond_precision() {
+        let dt = datetime!(1990-03-04 5:06:07.89);
+        assert_eq!(PrimitiveDateTime::try_from(DateTime::from(dt)).unwrap(), dt);
+        assert!(matches!(
+            PrimitiveDateTime::try_from(DateTime::from(f64::NAN)),
+            Err(ConversionError::NullValue)
+        ));
+        assert!(matches!(
+            PrimitiveDateTime::try_from(DateTime::from(f64::INFINITY)),
+            Err(ConversionError::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn times_convert_to_and_from_times() {
+        let t = time!(13:14:15.161);
+        assert_eq!(time::Time::try_from(Time::from(t)).unwrap(), t);
+        assert_eq!(time::Time::try_from(Second::from(t)).unwrap(), time!(13:14:15));
+        assert_eq!(time::Time::try_from(Minute::from(t)).unwrap(), time!(13:14));
+        assert!(time::Time::try_from(Minute::from(24 * 60)).is_err());
+        for inf in [i32::MAX, -i32::MAX] {
+            assert!(matches!(
+                time::Time::try_from(Time::from(inf)),
+                Err(ConversionError::OutOfRange)
+            ));
+            assert!(matches!(
+                time::Time::try_from(Second::from(inf)),
+                Err(ConversionError::OutOfRange)
+            ));
+            assert!(matches!(
+                time::Time::try_from(Minute::from(inf)),
+                Err(ConversionError::OutOfRange)
+            ));
+        }
+        assert!(matches!(
+            time::Time::try_from(Time::from(i32::MIN)),
+            Err(ConversionError::NullValue)
+        ));
+    }
+
+    #[test]
+    fn timespans_convert_to_and_from_durations() {
+        let d = Duration::nanoseconds(-1_234_567_890);
+        assert_eq!(Duration::try_from(Timespan::try_from(d).unwrap()).unwrap(), d);
+        assert!(matches!(
+            Duration::try_from(Timespan::from_nanos(i64::MIN)),
+            Err(ConversionError::NullValue)
+        ));
+        assert!(matches!(
+            Duration::try_from(Timespan::from_nanos(i64::MAX)),
+            Err(ConversionError::OutOfRange)
+        ));
+        assert!(Timespan::try_from(Duration::nanoseconds(i64::MAX)).is_err());
+        assert!(Timespan::try_from(Duration::days(1_000_000)).is_err());
+    }
+}
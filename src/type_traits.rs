@@ -1,3 +1,4 @@
+use crate::error::ConversionError;
 use crate::k::K;
 use crate::k_type::*;
 
@@ -14,6 +15,18 @@ pub trait KValue: private::Sealed {
 
 pub trait KTyped {
     const K_TYPE: KTypeCode;
+
+    /// Checks that a K object can be viewed as this type.
+    fn check_type(k: &K) -> Result<(), ConversionError> {
+        if k.t == Self::K_TYPE {
+            Ok(())
+        } else {
+            Err(ConversionError::InvalidKCast {
+                from: k.t,
+                to: Self::K_TYPE,
+            })
+        }
+    }
 }
 
 /// Indicates a type that wraps a `K` object. This trait is sealed and can't be implemented
@@ -26,12 +39,19 @@ pub trait KObject: private::Sealed {
 }
 
 /// Indicates something that can be stored in a List. Basically this is
-/// All KValues and the Any type. Used to provide list concatenation functions.
+/// All KValues and the Any type, along with lists, dictionaries and tables, which are stored in a mixed list.
+/// Used to provide list concatenation functions.
 // This trait is sealed and can't be implemented from other crates.
 pub trait KListable: private::Sealed {
     type ListItem; //: std::fmt::Debug;
     const LIST_TYPE_CODE: KTypeCode;
     unsafe fn join_to(item: Self::ListItem, k: *mut K) -> *mut K;
+
+    /// Checks that every item of a list of this type can be viewed as the item type. Only needed for typed views
+    /// over mixed lists, as the type code of a simple list already says what its items are.
+    fn check_items(_list: &K) -> Result<(), ConversionError> {
+        Ok(())
+    }
 }
 
 pub(crate) mod private {
@@ -52,6 +72,13 @@ mod k_type_impls {
     use uuid::Uuid;
 
     macro_rules! impl_k_value {
+        // `js` takes the interned string itself, rather than a pointer to the value.
+        (@join_arg js, $item:ident) => {
+            $item.into()
+        };
+        (@join_arg $joiner:ident, $item:ident) => {
+            &$item as *const _ as *const _
+        };
         ($type:ident, Code = $typecode: ident, Ctor = $ctor:ident, Accessor = $accessor:ident) => {
             impl_k_value!(
                 $type,
@@ -83,7 +110,7 @@ mod k_type_impls {
                 const LIST_TYPE_CODE: KTypeCode = TypeCode::$typecode.as_list();
 
                 unsafe fn join_to(item: Self::ListItem, mut k: *mut K) -> *mut K {
-                    kapi::$joiner(&mut k, &item as *const _ as *const _)
+                    kapi::$joiner(&mut k, impl_k_value!(@join_arg $joiner, item))
                 }
             }
 
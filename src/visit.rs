@@ -0,0 +1,312 @@
+//! Walking nested K objects.
+use crate::any::Any;
+use crate::atom::Atom;
+use crate::copy::copy_or_share;
+use crate::date_time_types::*;
+use crate::dictionary::Dictionary;
+use crate::k::{Attr, K};
+use crate::k_error::KError;
+use crate::k_type::*;
+use crate::kbox::KBox;
+use crate::list::{as_slice_mut, List};
+use crate::symbol::Symbol;
+use crate::table::Table;
+use crate::type_traits::KObject;
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+/// Calls `f` with the items of a simple list. The list's attribute is only cleared if `f` changes the items, as the
+/// list may no longer be sorted, unique or grouped.
+unsafe fn visit_items_mut<T>(k: *mut K, f: impl FnOnce(&mut [T])) {
+    let before = if (*k).u != 0 {
+        Some((*k).list_bytes().to_vec())
+    } else {
+        None
+    };
+    f(as_slice_mut(k));
+    if before.is_some_and(|before| before != (*k).list_bytes()) {
+        (*k).set_attr(Attr::NONE);
+    }
+}
+
+macro_rules! visitors {
+    ($($(#[$attr:meta])* $t:ty, $atom:ident, $atom_mut:ident, $list:ident, $list_mut:ident = $atom_code:ident, $list_code:ident;)*) => {
+        /// Callbacks for each kind of value found by `walk`. Every method does nothing by default, so a visitor only
+        /// needs to implement the ones it is interested in.
+        ///
+        /// Atoms are passed to the `visit_*` methods, and simple lists to the `visit_*_list` methods as slices.
+        /// Mixed lists, dictionaries and tables are announced with an `enter_*` call, followed by the values they
+        /// contain, followed by a `leave_*` call.
+        ///
+        /// # Example
+        /// ```
+        /// use kdb::{parse_literal, walk, KVisitor};
+        ///
+        /// #[derive(Default)]
+        /// struct SumLongs(i64);
+        ///
+        /// impl KVisitor for SumLongs {
+        ///     fn visit_long(&mut self, atom: &kdb::Atom<i64>) {
+        ///         self.0 += atom.value();
+        ///     }
+        ///
+        ///     fn visit_long_list(&mut self, list: &[i64]) {
+        ///         self.0 += list.iter().sum::<i64>();
+        ///     }
+        /// }
+        ///
+        /// let mut sum = SumLongs::default();
+        /// walk(&parse_literal("(1;`a`b!(2 3;4);([] c:5 6))").unwrap(), &mut sum);
+        /// assert_eq!(sum.0, 21);
+        /// ```
+        #[allow(unused_variables)]
+        pub trait KVisitor {
+            $(
+                $(#[$attr])*
+                #[doc = concat!("Called for `", stringify!($t), "` atoms.")]
+                fn $atom(&mut self, atom: &Atom<$t>) {}
+                $(#[$attr])*
+                #[doc = concat!("Called for simple lists of `", stringify!($t), "`.")]
+                fn $list(&mut self, list: &[$t]) {}
+            )*
+            /// Called before the items of a mixed list are visited.
+            fn enter_mixed_list(&mut self, list: &List<Any>) {}
+            /// Called after the items of a mixed list have been visited.
+            fn leave_mixed_list(&mut self, list: &List<Any>) {}
+            /// Called before the keys and then the values of a dictionary are visited.
+            fn enter_dict(&mut self, dict: &Dictionary) {}
+            /// Called after the keys and values of a dictionary have been visited.
+            fn leave_dict(&mut self, dict: &Dictionary) {}
+            /// Called before each column of a table is visited.
+            fn enter_table(&mut self, table: &Table) {}
+            /// Called after the columns of a table have been visited.
+            fn leave_table(&mut self, table: &Table) {}
+            /// Called for errors.
+            fn visit_error(&mut self, error: &KError) {}
+            /// Called for anything else, such as the generic null, enumerations and functions.
+            fn visit_other(&mut self, value: &Any) {}
+        }
+
+        /// The mutable version of `KVisitor`, used with `walk_mut` to change values in place.
+        #[allow(unused_variables)]
+        pub trait KVisitorMut {
+            $(
+                $(#[$attr])*
+                #[doc = concat!("Called for `", stringify!($t), "` atoms.")]
+                fn $atom_mut(&mut self, atom: &mut Atom<$t>) {}
+                $(#[$attr])*
+                #[doc = concat!("Called for simple lists of `", stringify!($t), "`.")]
+                fn $list_mut(&mut self, list: &mut [$t]) {}
+            )*
+            /// Called before the items of a mixed list are visited.
+            fn enter_mixed_list(&mut self, list: &mut List<Any>) {}
+            /// Called after the items of a mixed list have been visited.
+            fn leave_mixed_list(&mut self, list: &mut List<Any>) {}
+            /// Called before the keys and then the values of a dictionary are visited.
+            fn enter_dict(&mut self, dict: &mut Dictionary) {}
+            /// Called after the keys and values of a dictionary have been visited.
+            fn leave_dict(&mut self, dict: &mut Dictionary) {}
+            /// Called before each column of a table is visited.
+            fn enter_table(&mut self, table: &mut Table) {}
+            /// Called after the columns of a table have been visited.
+            fn leave_table(&mut self, table: &mut Table) {}
+            /// Called for errors.
+            fn visit_error(&mut self, error: &mut KError) {}
+            /// Called for anything else, such as the generic null, enumerations and functions.
+            fn visit_other(&mut self, value: &mut Any) {}
+        }
+
+        /// Visits a value and everything nested inside it, depth first.
+        pub fn walk(value: &Any, visitor: &mut impl KVisitor) {
+            unsafe {
+                let k = value.k_ptr();
+                match (*k).t {
+                    $(
+                        $(#[$attr])*
+                        $atom_code => visitor.$atom(&*(k as *const Atom<$t>)),
+                        $(#[$attr])*
+                        $list_code => visitor.$list((*(k as *const List<$t>)).as_slice()),
+                    )*
+                    MIXED_LIST => {
+                        let list = &*(k as *const List<Any>);
+                        visitor.enter_mixed_list(list);
+                        for item in list.iter() {
+                            walk(item, visitor);
+                        }
+                        visitor.leave_mixed_list(list);
+                    }
+                    DICT => {
+                        let dict = &*(k as *const Dictionary);
+                        visitor.enter_dict(dict);
+                        walk(dict.keys_any(), visitor);
+                        walk(dict.values_any(), visitor);
+                        visitor.leave_dict(dict);
+                    }
+                    TABLE => {
+                        let table = &*(k as *const Table);
+                        visitor.enter_table(table);
+                        for column in table.columns().iter() {
+                            walk(column, visitor);
+                        }
+                        visitor.leave_table(table);
+                    }
+                    ERROR => visitor.visit_error(&*(k as *const KError)),
+                    _ => visitor.visit_other(value),
+                }
+            }
+        }
+
+        /// Visits a value and everything nested inside it, depth first, allowing the visitor to change them.
+        ///
+        /// Lists shared with other K objects are changed for every object that holds them. Use `deep_clone` first
+        /// if that isn't wanted. The attribute of a list, such as `s#` or `u#`, is only removed if the visitor changes
+        /// the list, so walking a sorted key list without changing it keeps it sorted.
+        pub fn walk_mut(value: &mut Any, visitor: &mut impl KVisitorMut) {
+            unsafe {
+                let k = value.k_ptr_mut();
+                match (*k).t {
+                    $(
+                        $(#[$attr])*
+                        $atom_code => visitor.$atom_mut(&mut *(k as *mut Atom<$t>)),
+                        $(#[$attr])*
+                        $list_code => visit_items_mut(k, |items| visitor.$list_mut(items)),
+                    )*
+                    MIXED_LIST => {
+                        visitor.enter_mixed_list(&mut *(k as *mut List<Any>));
+                        // the items are changed in place, so compare the values rather than the item pointers.
+                        let before = if (*k).u != 0 { Some(KBox::<Any>::from_raw(copy_or_share(&*k))) } else { None };
+                        for item in as_slice_mut::<KBox<Any>>(k) {
+                            walk_mut(item, visitor);
+                        }
+                        if before.is_some_and(|before| *before != *(k as *const Any)) {
+                            (*k).set_attr(Attr::NONE);
+                        }
+                        visitor.leave_mixed_list(&mut *(k as *mut List<Any>));
+                    }
+                    DICT => {
+                        visitor.enter_dict(&mut *(k as *mut Dictionary));
+                        walk_mut(&mut *((*k).union.dict.k as *mut Any), visitor);
+                        walk_mut(&mut *((*k).union.dict.v as *mut Any), visitor);
+                        visitor.leave_dict(&mut *(k as *mut Dictionary));
+                    }
+                    TABLE => {
+                        visitor.enter_table(&mut *(k as *mut Table));
+                        for column in as_slice_mut::<KBox<Any>>((*(*k).union.k0).union.dict.v) {
+                            walk_mut(column, visitor);
+                        }
+                        visitor.leave_table(&mut *(k as *mut Table));
+                    }
+                    ERROR => visitor.visit_error(&mut *(k as *mut KError)),
+                    _ => visitor.visit_other(&mut *(k as *mut K as *mut Any)),
+                }
+            }
+        }
+    };
+}
+
+visitors! {
+    bool, visit_bool, visit_bool_mut, visit_bool_list, visit_bool_list_mut = BOOLEAN_ATOM, BOOLEAN_LIST;
+    #[cfg(feature = "uuid")]
+    Uuid, visit_guid, visit_guid_mut, visit_guid_list, visit_guid_list_mut = GUID_ATOM, GUID_LIST;
+    u8, visit_byte, visit_byte_mut, visit_byte_list, visit_byte_list_mut = BYTE_ATOM, BYTE_LIST;
+    i16, visit_short, visit_short_mut, visit_short_list, visit_short_list_mut = SHORT_ATOM, SHORT_LIST;
+    i32, visit_int, visit_int_mut, visit_int_list, visit_int_list_mut = INT_ATOM, INT_LIST;
+    i64, visit_long, visit_long_mut, visit_long_list, visit_long_list_mut = LONG_ATOM, LONG_LIST;
+    f32, visit_real, visit_real_mut, visit_real_list, visit_real_list_mut = REAL_ATOM, REAL_LIST;
+    f64, visit_float, visit_float_mut, visit_float_list, visit_float_list_mut = FLOAT_ATOM, FLOAT_LIST;
+    i8, visit_char, visit_char_mut, visit_char_list, visit_char_list_mut = CHAR_ATOM, CHAR_LIST;
+    Symbol, visit_symbol, visit_symbol_mut, visit_symbol_list, visit_symbol_list_mut = SYMBOL_ATOM, SYMBOL_LIST;
+    Timestamp, visit_timestamp, visit_timestamp_mut, visit_timestamp_list, visit_timestamp_list_mut = TIMESTAMP_ATOM, TIMESTAMP_LIST;
+    Month, visit_month, visit_month_mut, visit_month_list, visit_month_list_mut = MONTH_ATOM, MONTH_LIST;
+    Date, visit_date, visit_date_mut, visit_date_list, visit_date_list_mut = DATE_ATOM, DATE_LIST;
+    DateTime, visit_datetime, visit_datetime_mut, visit_datetime_list, visit_datetime_list_mut = DATE_TIME_ATOM, DATE_TIME_LIST;
+    Timespan, visit_timespan, visit_timespan_mut, visit_timespan_list, visit_timespan_list_mut = TIMESPAN_ATOM, TIMESPAN_LIST;
+    Minute, visit_minute, visit_minute_mut, visit_minute_list, visit_minute_list_mut = MINUTE_ATOM, MINUTE_LIST;
+    Second, visit_second, visit_second_mut, visit_second_list, visit_second_list_mut = SECOND_ATOM, SECOND_LIST;
+    Time, visit_time, visit_time_mut, visit_time_list, visit_time_list_mut = TIME_ATOM, TIME_LIST;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_literal, symbol};
+
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl KVisitor for Trace {
+        fn visit_long(&mut self, atom: &Atom<i64>) {
+            self.0.push(atom.value().to_string());
+        }
+        fn visit_long_list(&mut self, list: &[i64]) {
+            self.0.push(format!("{:?}", list));
+        }
+        fn visit_symbol_list(&mut self, list: &[Symbol]) {
+            self.0.push(format!("{} syms", list.len()));
+        }
+        fn enter_mixed_list(&mut self, _: &List<Any>) {
+            self.0.push("(".to_owned());
+        }
+        fn leave_mixed_list(&mut self, _: &List<Any>) {
+            self.0.push(")".to_owned());
+        }
+        fn enter_dict(&mut self, _: &Dictionary) {
+            self.0.push("{".to_owned());
+        }
+        fn leave_dict(&mut self, _: &Dictionary) {
+            self.0.push("}".to_owned());
+        }
+        fn enter_table(&mut self, table: &Table) {
+            self.0.push(format!("table {}", table.len()));
+        }
+        fn visit_other(&mut self, _: &Any) {
+            self.0.push("other".to_owned());
+        }
+    }
+
+    #[test]
+    fn walk_visits_nested_values_in_order() {
+        let mut trace = Trace::default();
+        walk(&parse_literal("(1;`a`b!(2 3;::);([] c:4 5))").unwrap(), &mut trace);
+        assert_eq!(
+            trace.0,
+            ["(", "1", "{", "2 syms", "(", "[2, 3]", "other", ")", "}", "table 2", "[4, 5]", ")"]
+        );
+    }
+
+    struct Double;
+
+    impl KVisitorMut for Double {
+        fn visit_long_mut(&mut self, atom: &mut Atom<i64>) {
+            atom.set_value(atom.value() * 2);
+        }
+        fn visit_long_list_mut(&mut self, list: &mut [i64]) {
+            list.iter_mut().for_each(|x| *x *= 2);
+        }
+        fn visit_symbol_mut(&mut self, atom: &mut Atom<Symbol>) {
+            atom.set_value(symbol("z"));
+        }
+    }
+
+    #[test]
+    fn walk_mut_changes_values_in_place() {
+        let mut a = parse_literal("(1;`a`b!(2 3;`y);([] c:4 5))").unwrap();
+        walk_mut(&mut a, &mut Double);
+        assert_eq!(a, parse_literal("(2;`a`b!(4 6;`z);([] c:8 10))").unwrap());
+    }
+
+    #[test]
+    fn walk_mut_keeps_attributes_of_unchanged_lists() {
+        let mut a = parse_literal("`a`b!1 2").unwrap();
+        let keys = unsafe { (*a.k_ptr()).union.dict.k };
+        unsafe { (*keys).set_attr(Attr::UNIQUE) };
+        walk_mut(&mut a, &mut Double);
+        assert!(unsafe { (*keys).attr().unique() });
+
+        let mut a = parse_literal("1 2 3").unwrap();
+        unsafe { (*a.k_ptr_mut()).set_attr(Attr::SORTED) };
+        walk_mut(&mut a, &mut Double);
+        assert!(!unsafe { (*a.k_ptr()).attr().sorted() });
+        assert_eq!(a, parse_literal("2 4 6").unwrap());
+    }
+}